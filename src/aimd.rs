@@ -0,0 +1,204 @@
+use clap::Arg;
+use time::{self, Timespec};
+
+use ::{parse_arg, parse_opt_arg, RemoteGenericCongAvoidAlg, NetworkStatus};
+use GenericCongAvoidFlow;
+use GenericCongAvoidMeasurements;
+
+/// A parameterized AIMD/MIMD window, configured entirely from the command line.
+///
+/// Every RTT the window grows by `ai_per_rtt` packets, plus `ai_per_ack` packets per acked
+/// packet, and is then multiplied by `mi`. On loss it is multiplied by `md`, and it never
+/// falls below `min_cwnd` packets. If `util_target` is set, the SDCCP controller is polled
+/// once per RTT: below the target the increase is scaled up by
+/// `1 + util_gain * (util_target - utilization)`, above it the window is cut once by
+/// `util_gain * (utilization - util_target)`.
+pub struct Aimd {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    cwnd: f64,
+
+    ai_per_rtt: f64,
+    ai_per_ack: f64,
+    mi: f64,
+    md: f64,
+    min_cwnd: f64,
+    util_target: Option<f64>,
+    util_gain: f64,
+
+    utilization: Option<f64>,
+    last_poll: Option<Timespec>,
+    server_url: String,
+}
+
+impl Default for Aimd {
+    fn default() -> Self {
+        Aimd {
+            logger: None,
+            mss: 0,
+            cwnd: 0.0,
+
+            ai_per_rtt: 1.0,
+            ai_per_ack: 0.0,
+            mi: 1.0,
+            md: 0.5,
+            min_cwnd: 2.0,
+            util_target: None,
+            util_gain: 1.0,
+
+            utilization: None,
+            last_poll: None,
+            server_url: String::new(),
+        }
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Aimd {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "aimd"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("aimd_ai_per_rtt")
+                .long("aimd_ai_per_rtt")
+                .help("Additive increase per RTT, in packets")
+                .default_value("1"),
+            Arg::with_name("aimd_ai_per_ack")
+                .long("aimd_ai_per_ack")
+                .help("Additive increase per acked packet, in packets")
+                .default_value("0"),
+            Arg::with_name("aimd_mi")
+                .long("aimd_mi")
+                .help("Multiplicative increase factor per RTT (1 disables MI)")
+                .default_value("1"),
+            Arg::with_name("aimd_md")
+                .long("aimd_md")
+                .help("Fraction of the window kept on loss")
+                .default_value("0.5"),
+            Arg::with_name("aimd_min_cwnd")
+                .long("aimd_min_cwnd")
+                .help("Minimum congestion window, in packets")
+                .default_value("2"),
+            Arg::with_name("aimd_util_target")
+                .long("aimd_util_target")
+                .takes_value(true)
+                .help("Poll the SDCCP controller and scale the window update towards this link utilization"),
+            Arg::with_name("aimd_util_gain")
+                .long("aimd_util_gain")
+                .help("Gain of the utilization scaling term")
+                .default_value("1"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Aimd {
+            ai_per_rtt: parse_arg(&matches, "aimd_ai_per_rtt")?,
+            ai_per_ack: parse_arg(&matches, "aimd_ai_per_ack")?,
+            mi: parse_arg(&matches, "aimd_mi")?,
+            md: parse_arg(&matches, "aimd_md")?,
+            min_cwnd: parse_arg(&matches, "aimd_min_cwnd")?,
+            util_target: parse_opt_arg(&matches, "aimd_util_target")?,
+            util_gain: parse_arg(&matches, "aimd_util_gain")?,
+            ..Default::default()
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                sock_id: u32) -> Self::Flow {
+        Aimd {
+            logger,
+            mss,
+            cwnd: f64::from(init_cwnd),
+
+            ai_per_rtt: self.ai_per_rtt,
+            ai_per_ack: self.ai_per_ack,
+            mi: self.mi,
+            md: self.md,
+            min_cwnd: self.min_cwnd,
+            util_target: self.util_target,
+            util_gain: self.util_gain,
+
+            utilization: None,
+            last_poll: None,
+            server_url: NetworkStatus::url(sock_id),
+        }
+    }
+}
+
+impl Aimd {
+    /// Poll the controller if an RTT has passed since the last poll. Returns whether
+    /// the utilization was refreshed.
+    fn poll_utilization(&mut self, rtt: u32) -> bool {
+        let now = time::now().to_timespec();
+        let due = self.last_poll
+            .is_none_or(|t| now - t >= time::Duration::microseconds(i64::from(rtt)));
+        if !due {
+            return false;
+        }
+
+        self.last_poll = Some(now);
+        self.utilization = Some(f64::from(NetworkStatus::fetch(&self.server_url).link_utilization));
+        true
+    }
+
+    fn clamp_cwnd(&mut self) {
+        let min_cwnd = self.min_cwnd * f64::from(self.mss);
+        if self.cwnd < min_cwnd {
+            self.cwnd = min_cwnd;
+        }
+    }
+}
+
+impl GenericCongAvoidFlow for Aimd {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn use_remote(&self) -> bool {
+        false
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        let mut scale = 1.0;
+        if let Some(target) = self.util_target {
+            let fresh = self.poll_utilization(m.rtt);
+            let utilization = self.utilization.unwrap();
+            if utilization > target {
+                // cut once per controller poll, and don't grow in between
+                if fresh {
+                    self.cwnd *= (1.0 - self.util_gain * (utilization - target)).max(self.md);
+                    self.clamp_cwnd();
+                }
+                return;
+            }
+
+            scale = 1.0 + self.util_gain * (target - utilization);
+        }
+
+        let mss = f64::from(self.mss);
+        let acked = f64::from(m.acked);
+        // fraction of an RTT's worth of data acked by this report
+        let rtt_fraction = acked / self.cwnd;
+
+        self.cwnd += scale * self.ai_per_rtt * mss * rtt_fraction;
+        self.cwnd += scale * self.ai_per_ack * acked;
+        self.cwnd *= self.mi.powf(scale * rtt_fraction);
+        self.clamp_cwnd();
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        self.cwnd *= self.md;
+        self.clamp_cwnd();
+
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "aimd reduction"; "cwnd (pkts)" => self.cwnd as u32 / self.mss);
+        }
+    }
+}
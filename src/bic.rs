@@ -0,0 +1,187 @@
+use clap::Arg;
+
+use ::{parse_arg, FlowHandoff, RemoteGenericCongAvoidAlg, GenericCongAvoidFlow,
+       GenericCongAvoidMeasurements, DEFAULT_SS_THRESH};
+
+/// Binary search factor.
+const BIC_B: f64 = 4.0;
+/// Per-RTT increase, in packets, when close to the last maximum.
+const SMIN: f64 = 0.2;
+
+/// BIC: binary-search the window between the window before the last loss and the
+/// current window, then probe beyond it.
+///
+/// Increases are computed per RTT (in packets) and spread over the acked bytes, as with
+/// Reno. Up to `low_window` packets, BIC behaves like Reno.
+pub struct Bic {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    init_cwnd: f64,
+    cwnd: f64,
+
+    beta: f64,
+    max_increment: f64,
+    low_window: f64,
+    fast_convergence: bool,
+
+    last_max_cwnd: f64,
+}
+
+impl Default for Bic {
+    fn default() -> Self {
+        Bic {
+            logger: None,
+            mss: 0,
+            init_cwnd: 0.0,
+            cwnd: 0.0,
+
+            beta: 0.8,
+            max_increment: 16.0,
+            low_window: 14.0,
+            fast_convergence: true,
+
+            last_max_cwnd: 0.0,
+        }
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Bic {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "bic"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("bic_beta")
+                .long("bic_beta")
+                .help("Fraction of the window kept on loss")
+                .default_value("0.8"),
+            Arg::with_name("bic_max_increment")
+                .long("bic_max_increment")
+                .help("Maximum increase per RTT, in packets")
+                .default_value("16"),
+            Arg::with_name("bic_low_window")
+                .long("bic_low_window")
+                .help("Window, in packets, up to which BIC behaves like Reno")
+                .default_value("14"),
+            Arg::with_name("bic_no_fast_convergence")
+                .long("bic_no_fast_convergence")
+                .help("Do not lower the last maximum when losses happen below it"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Bic {
+            beta: parse_arg(&matches, "bic_beta")?,
+            max_increment: parse_arg(&matches, "bic_max_increment")?,
+            low_window: parse_arg(&matches, "bic_low_window")?,
+            fast_convergence: !matches.is_present("bic_no_fast_convergence"),
+            ..Default::default()
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                _sock_id: u32) -> Self::Flow {
+        Bic {
+            logger,
+            mss,
+            init_cwnd: f64::from(init_cwnd),
+            cwnd: f64::from(init_cwnd),
+
+            beta: self.beta,
+            max_increment: self.max_increment,
+            low_window: self.low_window,
+            fast_convergence: self.fast_convergence,
+            ..Default::default()
+        }
+    }
+}
+
+impl Bic {
+    /// Increase per RTT, in packets.
+    fn increment(&self) -> f64 {
+        let w = self.cwnd / f64::from(self.mss);
+        let last_max = self.last_max_cwnd / f64::from(self.mss);
+        if w <= self.low_window || last_max == 0.0 {
+            return 1.0;
+        }
+
+        if w < last_max {
+            // binary search increase
+            let dist = (last_max - w) / BIC_B;
+            if dist > self.max_increment {
+                self.max_increment
+            } else if dist <= 1.0 {
+                SMIN
+            } else {
+                dist
+            }
+        } else if w < last_max + BIC_B {
+            // slow start from the last maximum
+            SMIN
+        } else if w < last_max + self.max_increment * (BIC_B - 1.0) {
+            (w - last_max) / (BIC_B - 1.0)
+        } else {
+            // linear max probing
+            self.max_increment
+        }
+    }
+}
+
+impl GenericCongAvoidFlow for Bic {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn use_remote(&self) -> bool {
+        false
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        self.cwnd += self.increment() * f64::from(self.mss) * (f64::from(m.acked) / self.cwnd);
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        if self.fast_convergence && self.cwnd < self.last_max_cwnd {
+            self.last_max_cwnd = self.cwnd * (1.0 + self.beta) / 2.0;
+        } else {
+            self.last_max_cwnd = self.cwnd;
+        }
+
+        if self.cwnd / f64::from(self.mss) <= self.low_window {
+            self.cwnd /= 2.0;
+        } else {
+            self.cwnd *= self.beta;
+        }
+
+        if self.cwnd <= self.init_cwnd {
+            self.cwnd = self.init_cwnd;
+        }
+
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "bic reduction";
+                "last_max (pkts)" => self.last_max_cwnd as u32 / self.mss,
+                "cwnd (pkts)" => self.cwnd as u32 / self.mss,
+            );
+        }
+    }
+
+    fn reset(&mut self) {
+        self.last_max_cwnd = 0.0;
+    }
+
+    /// A lowered slow start threshold marks where the flow last saw loss, which is where
+    /// the binary search should converge.
+    fn import_state(&mut self, state: &FlowHandoff) {
+        self.set_cwnd(state.cwnd);
+        if state.ss_thresh < DEFAULT_SS_THRESH {
+            self.last_max_cwnd = f64::from(state.ss_thresh);
+        }
+    }
+}
@@ -0,0 +1,7 @@
+extern crate generic_cong_avoid;
+extern crate portus;
+
+fn main() {
+    let log = portus::algs::make_logger();
+    generic_cong_avoid::registry::start_selected("CCP generic congestion avoidance", log);
+}
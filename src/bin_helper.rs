@@ -3,19 +3,16 @@ use clap::Arg;
 use portus;
 use portus::ipc::{BackendBuilder, Blocking};
 use slog;
-use std;
+use std::rc::Rc;
 use time;
 use {
-    Alg, RemoteGenericCongAvoidAlg, GenericCongAvoidConfigReport,
+    parse_arg, parse_opt_arg, program_specs, Alg, ControllerExit, Guardrails, RemoteGenericCongAvoidAlg, GenericCongAvoidConfigReport,
     GenericCongAvoidConfigSS, DEFAULT_SS_THRESH,
 };
 
-pub fn make_args<A: RemoteGenericCongAvoidAlg>(
-    name: &str,
-    logger: impl Into<Option<slog::Logger>>,
-) -> Result<(Alg<A>, String), std::num::ParseIntError> {
-    let ss_thresh_default = format!("{}", DEFAULT_SS_THRESH);
-    let matches = clap::App::new(name)
+/// The command line shared by all algorithms, without algorithm-specific arguments.
+pub(crate) fn app<'a, 'b>(name: &str, ss_thresh_default: &'a str) -> clap::App<'a, 'b> {
+    clap::App::new(name)
         .version("0.2.0")
         .author("Akshay Narayan <akshayn@mit.edu>")
         .about("CCP implementation of a congestion avoidance algorithm")
@@ -31,10 +28,34 @@ pub fn make_args<A: RemoteGenericCongAvoidAlg>(
         .arg(Arg::with_name("ss_thresh")
              .long("ss_thresh")
              .help("Sets the slow start threshold, in bytes")
-             .default_value(&ss_thresh_default))
+             .default_value(ss_thresh_default))
         .arg(Arg::with_name("ss_in_fold")
              .long("ss_in_fold")
              .help("Implement slow start in the datapath"))
+        .arg(Arg::with_name("ss_in_ccp")
+             .long("ss_in_ccp")
+             .conflicts_with("ss_in_fold")
+             .help("Implement slow start in CCP, on each report"))
+        .arg(Arg::with_name("ss_hystart")
+             .long("ss_hystart")
+             .requires("ss_in_ccp")
+             .help("End CCP slow start with HyStart++ (RFC 9406)"))
+        .arg(Arg::with_name("ss_controller_exit")
+             .long("ss_controller_exit")
+             .requires("ss_in_ccp")
+             .help("End CCP slow start when the SDCCP controller reports a busy bottleneck"))
+        .arg(Arg::with_name("ss_exit_utilization")
+             .long("ss_exit_utilization")
+             .default_value("0.9")
+             .help("Link utilization at which --ss_controller_exit ends slow start"))
+        .arg(Arg::with_name("ss_exit_queue")
+             .long("ss_exit_queue")
+             .default_value("15000")
+             .help("Queue length, in bytes, beyond which --ss_controller_exit ends slow start"))
+        .arg(Arg::with_name("ss_delay_exit")
+             .long("ss_delay_exit")
+             .requires("ss_in_fold")
+             .help("End datapath slow start when RTT samples rise above the minimum RTT (HyStart)"))
         .arg(Arg::with_name("report_per_ack")
              .long("per_ack")
              .help("Specifies that the datapath should send a measurement upon every ACK"))
@@ -42,57 +63,141 @@ pub fn make_args<A: RemoteGenericCongAvoidAlg>(
              .long("report_interval_ms")
              .short("i")
              .takes_value(true))
+        .arg(Arg::with_name("report_per_epoch")
+             .long("report_controller_epoch")
+             .help("Report once per SDCCP controller update, learning the update period from the controller's timestamps"))
+        .arg(Arg::with_name("controller_epoch_ms")
+             .long("controller_epoch_ms")
+             .takes_value(true)
+             .requires("report_per_epoch")
+             .help("The SDCCP controller's update period, instead of learning it"))
+        .arg(Arg::with_name("report_adaptive")
+             .long("report_adaptive")
+             .help("Report frequently around congestion events and sparsely in steady state"))
+        .arg(Arg::with_name("report_adaptive_min_ms")
+             .long("report_adaptive_min_ms")
+             .default_value("5")
+             .help("Shortest adaptive report interval, used around congestion events"))
+        .arg(Arg::with_name("report_adaptive_max_ms")
+             .long("report_adaptive_max_ms")
+             .default_value("100")
+             .help("Longest adaptive report interval, used in steady state"))
         .group(clap::ArgGroup::with_name("interval")
-             .args(&["report_per_ack", "report_per_interval"])
+             .args(&["report_per_ack", "report_per_interval", "report_per_epoch", "report_adaptive"])
              .required(false))
         .arg(Arg::with_name("compensate_update")
              .long("compensate_update")
              .help("Scale the congestion window update during slow start to compensate for reporting delay"))
+        .arg(Arg::with_name("min_cwnd_pkts")
+             .long("min_cwnd_pkts")
+             .default_value("1")
+             .help("Never set the congestion window below this many packets"))
+        .arg(Arg::with_name("max_cwnd_pkts")
+             .long("max_cwnd_pkts")
+             .takes_value(true)
+             .help("Never set the congestion window above this many packets"))
+        .arg(Arg::with_name("min_rate")
+             .long("min_rate")
+             .takes_value(true)
+             .help("Keep the congestion window large enough for this rate over the smoothed RTT, in bytes per second"))
+        .arg(Arg::with_name("max_rate")
+             .long("max_rate")
+             .takes_value(true)
+             .help("Pace the flow at no more than this rate, in bytes per second"))
         .arg(Arg::with_name("deficit_timeout")
              .long("deficit_timeout")
              .default_value("0")
              .help("Number of RTTs to wait after a loss event to allow further CWND reductions. \
                    Default 0 means CWND deficit counting is enforced strictly with no timeout."))
+}
+
+pub fn make_args<A: RemoteGenericCongAvoidAlg>(
+    name: &str,
+    logger: impl Into<Option<slog::Logger>>,
+) -> Result<(Alg<A>, String), String> {
+    let ss_thresh_default = format!("{}", DEFAULT_SS_THRESH);
+    let matches = app(name, &ss_thresh_default)
         .args(&A::args())
         .get_matches();
 
+    alg_from_matches(matches, logger)
+}
+
+pub(crate) fn alg_from_matches<A: RemoteGenericCongAvoidAlg>(
+    matches: clap::ArgMatches,
+    logger: impl Into<Option<slog::Logger>>,
+) -> Result<(Alg<A>, String), String> {
     let ipc = String::from(matches.value_of("ipc").unwrap());
 
     Ok((
         Alg {
-            ss_thresh: u32::from_str_radix(matches.value_of("ss_thresh").unwrap(), 10)?,
-            init_cwnd: u32::from_str_radix(matches.value_of("init_cwnd").unwrap(), 10)?,
+            ss_thresh: parse_arg(&matches, "ss_thresh")?,
+            init_cwnd: parse_arg(&matches, "init_cwnd")?,
             report_option: if matches.is_present("report_per_ack") {
                 GenericCongAvoidConfigReport::Ack
             } else if matches.is_present("report_per_interval") {
                 GenericCongAvoidConfigReport::Interval(time::Duration::milliseconds(
-                    matches
-                        .value_of("report_per_interval")
-                        .unwrap()
-                        .parse()
-                        .unwrap(),
+                    parse_arg(&matches, "report_per_interval")?,
                 ))
+            } else if matches.is_present("report_per_epoch") {
+                GenericCongAvoidConfigReport::ControllerEpoch(
+                    parse_opt_arg(&matches, "controller_epoch_ms")?.map(time::Duration::milliseconds),
+                )
+            } else if matches.is_present("report_adaptive") {
+                adaptive_report(&matches)?
             } else {
                 GenericCongAvoidConfigReport::Rtt
             },
             ss: if matches.is_present("ss_in_fold") {
                 GenericCongAvoidConfigSS::Datapath
-            } else {
+            } else if matches.is_present("ss_in_ccp") {
                 GenericCongAvoidConfigSS::Ccp
+            } else {
+                GenericCongAvoidConfigSS::Off
+            },
+            ss_delay_exit: matches.is_present("ss_delay_exit"),
+            ss_hystart: matches.is_present("ss_hystart"),
+            ss_controller_exit: if matches.is_present("ss_controller_exit") {
+                Some(ControllerExit {
+                    utilization: parse_arg(&matches, "ss_exit_utilization")?,
+                    queue: parse_arg(&matches, "ss_exit_queue")?,
+                })
+            } else {
+                None
             },
             use_compensation: matches.is_present("compensate_update"),
-            deficit_timeout: u32::from_str_radix(matches.value_of("deficit_timeout").unwrap(), 10)?,
+            guardrails: Guardrails {
+                min_cwnd_pkts: parse_arg(&matches, "min_cwnd_pkts")?,
+                max_cwnd_pkts: parse_opt_arg(&matches, "max_cwnd_pkts")?,
+                min_rate: parse_opt_arg(&matches, "min_rate")?,
+                max_rate: parse_opt_arg(&matches, "max_rate")?,
+            },
+            deficit_timeout: parse_arg(&matches, "deficit_timeout")?,
             logger: logger.into(),
-            alg: A::with_args(matches),
+            alg: A::with_args(matches)?,
+            specs: Rc::new(program_specs::<A>()),
         },
         ipc,
     ))
 }
 
-pub fn start<A: RemoteGenericCongAvoidAlg>(ipc: &str, log: slog::Logger, alg: Alg<A>)
-where
-    A: 'static,
-{
+fn adaptive_report(matches: &clap::ArgMatches) -> Result<GenericCongAvoidConfigReport, String> {
+    let min: i64 = parse_arg(matches, "report_adaptive_min_ms")?;
+    let max: i64 = parse_arg(matches, "report_adaptive_max_ms")?;
+    if min <= 0 {
+        return Err(String::from("--report_adaptive_min_ms must be positive"));
+    }
+    if min > max {
+        return Err(String::from("--report_adaptive_min_ms must not exceed --report_adaptive_max_ms"));
+    }
+
+    Ok(GenericCongAvoidConfigReport::Adaptive {
+        min: time::Duration::milliseconds(min),
+        max: time::Duration::milliseconds(max),
+    })
+}
+
+pub fn start<A: RemoteGenericCongAvoidAlg + 'static>(ipc: &str, log: slog::Logger, alg: Alg<A>) {
     match ipc {
         "unix" => {
             use portus::ipc::unix::Socket;
@@ -108,7 +213,7 @@ where
             )
             .unwrap();
         }
-        #[cfg(all(target_os = "linux"))]
+        #[cfg(target_os = "linux")]
         "netlink" => {
             use portus::ipc::netlink::Socket;
             let b = Socket::<Blocking>::new()
@@ -123,7 +228,7 @@ where
             )
             .unwrap();
         }
-        #[cfg(all(target_os = "linux"))]
+        #[cfg(target_os = "linux")]
         "char" => {
             use portus::ipc::kp::Socket;
             let b = Socket::<Blocking>::new()
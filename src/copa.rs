@@ -0,0 +1,293 @@
+use std::collections::VecDeque;
+
+use clap::Arg;
+use time::{self, Timespec};
+
+use ::{parse_arg, FlowHandoff, RemoteGenericCongAvoidAlg, GenericCongAvoidFlow,
+       GenericCongAvoidMeasurements};
+
+pub const DEFAULT_DELTA: f64 = 0.5;
+
+/// Window over which RTTmin is tracked.
+const RTT_MIN_WINDOW_US: i64 = 10_000_000;
+/// Number of RTTs the queue must stay non-empty before switching to competitive mode.
+const COMPETITIVE_RTTS: i64 = 5;
+/// Number of RTTs cwnd must move in the same direction before velocity starts doubling.
+const VELOCITY_RTTS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Up,
+    Down,
+}
+
+/// Min (or max) of `(time, rtt)` samples over a sliding time window.
+#[derive(Default)]
+struct WindowedFilter {
+    samples: VecDeque<(Timespec, u32)>,
+}
+
+impl WindowedFilter {
+    fn update(&mut self, now: Timespec, rtt: u32, window_us: i64, keep_min: bool) {
+        while let Some(&(_, v)) = self.samples.back() {
+            if (keep_min && v >= rtt) || (!keep_min && v <= rtt) {
+                self.samples.pop_back();
+            } else {
+                break;
+            }
+        }
+
+        self.samples.push_back((now, rtt));
+        let horizon = now - time::Duration::microseconds(window_us);
+        while self.samples.len() > 1 && self.samples.front().is_some_and(|&(t, _)| t < horizon) {
+            self.samples.pop_front();
+        }
+    }
+
+    fn get(&self) -> Option<u32> {
+        self.samples.front().map(|&(_, v)| v)
+    }
+}
+
+/// Copa: converge to the target rate 1 / (delta * queueing delay).
+///
+/// cwnd moves towards the target by `velocity / (delta * cwnd)` packets per acked packet.
+/// When the queue never drains within a few RTTs (i.e. we compete with buffer-filling
+/// flows), `delta` is adapted AIMD-style instead of staying fixed.
+pub struct Copa {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    init_cwnd: f64,
+    cwnd: f64,
+
+    default_delta: f64,
+    delta: f64,
+    allow_competitive: bool,
+    competitive: bool,
+
+    srtt: f64,
+    rtt_min: WindowedFilter,
+    rtt_standing: WindowedFilter,
+    rtt_max: WindowedFilter,
+    last_empty_queue: Timespec,
+
+    velocity: f64,
+    direction: Direction,
+    same_direction_rtts: u32,
+    rtt_start: Timespec,
+    rtt_start_cwnd: f64,
+}
+
+impl Default for Copa {
+    fn default() -> Self {
+        let now = time::now().to_timespec();
+        Copa {
+            logger: None,
+            mss: 0,
+            init_cwnd: 0.0,
+            cwnd: 0.0,
+
+            default_delta: DEFAULT_DELTA,
+            delta: DEFAULT_DELTA,
+            allow_competitive: true,
+            competitive: false,
+
+            srtt: 0.0,
+            rtt_min: Default::default(),
+            rtt_standing: Default::default(),
+            rtt_max: Default::default(),
+            last_empty_queue: now,
+
+            velocity: 1.0,
+            direction: Direction::Up,
+            same_direction_rtts: 0,
+            rtt_start: now,
+            rtt_start_cwnd: 0.0,
+        }
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Copa {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "copa"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("copa_delta")
+                .long("copa_delta")
+                .help("Copa's delta: the target rate is 1 / (delta * queueing delay)")
+                .default_value("0.5"),
+            Arg::with_name("copa_default_mode_only")
+                .long("copa_default_mode_only")
+                .help("Never switch Copa to competitive mode"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Copa {
+            default_delta: parse_arg(&matches, "copa_delta")?,
+            allow_competitive: !matches.is_present("copa_default_mode_only"),
+            ..Default::default()
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                _sock_id: u32) -> Self::Flow {
+        Copa {
+            logger,
+            mss,
+            init_cwnd: f64::from(init_cwnd),
+            cwnd: f64::from(init_cwnd),
+            default_delta: self.default_delta,
+            delta: self.default_delta,
+            allow_competitive: self.allow_competitive,
+            rtt_start_cwnd: f64::from(init_cwnd),
+            ..Default::default()
+        }
+    }
+}
+
+impl Copa {
+    /// The min and standing filters take the smallest RTT of the report, the max filter
+    /// its largest, and srtt the latest sample.
+    fn update_rtt(&mut self, now: Timespec, m: &GenericCongAvoidMeasurements) {
+        if self.srtt == 0.0 {
+            self.srtt = f64::from(m.rtt);
+        } else {
+            self.srtt = 0.875 * self.srtt + 0.125 * f64::from(m.rtt);
+        }
+
+        let srtt = self.srtt as i64;
+        self.rtt_min.update(now, m.rtt_min, RTT_MIN_WINDOW_US, true);
+        self.rtt_standing.update(now, m.rtt_min, srtt / 2, true);
+        self.rtt_max.update(now, m.rtt_max, srtt * COMPETITIVE_RTTS, false);
+    }
+
+    /// Switch between default and competitive mode based on whether the queue
+    /// has been nearly empty at least once in the last few RTTs.
+    fn update_mode(&mut self, now: Timespec, rtt_min: f64, rtt_standing: f64) {
+        let rtt_max = f64::from(self.rtt_max.get().unwrap_or(0));
+        if rtt_standing < rtt_min + 0.1 * (rtt_max - rtt_min) {
+            self.last_empty_queue = now;
+        }
+
+        let competitive = self.allow_competitive
+            && (now - self.last_empty_queue)
+                > time::Duration::microseconds(self.srtt as i64 * COMPETITIVE_RTTS);
+        if competitive != self.competitive {
+            self.delta = self.default_delta;
+            self.competitive = competitive;
+            if let Some(log) = self.logger.as_ref() {
+                debug!(log, "copa mode switch"; "competitive" => competitive);
+            }
+        }
+    }
+
+    /// Once per RTT: update velocity, and in competitive mode additively increase 1/delta.
+    fn on_rtt_elapsed(&mut self, now: Timespec) {
+        if (now - self.rtt_start) < time::Duration::microseconds(self.srtt as i64) {
+            return;
+        }
+
+        let direction = if self.cwnd >= self.rtt_start_cwnd {
+            Direction::Up
+        } else {
+            Direction::Down
+        };
+
+        if direction == self.direction {
+            self.same_direction_rtts += 1;
+            if self.same_direction_rtts >= VELOCITY_RTTS {
+                self.velocity *= 2.0;
+            }
+        } else {
+            self.direction = direction;
+            self.same_direction_rtts = 0;
+            self.velocity = 1.0;
+        }
+
+        if self.competitive {
+            self.delta = 1.0 / (1.0 / self.delta + 1.0);
+        }
+
+        self.rtt_start = now;
+        self.rtt_start_cwnd = self.cwnd;
+    }
+}
+
+impl GenericCongAvoidFlow for Copa {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn use_remote(&self) -> bool {
+        false
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        if m.rtt == 0 {
+            return;
+        }
+
+        let now = time::now().to_timespec();
+        self.update_rtt(now, m);
+        let rtt_min = f64::from(self.rtt_min.get().unwrap_or(m.rtt));
+        let rtt_standing = f64::from(self.rtt_standing.get().unwrap_or(m.rtt));
+        self.update_mode(now, rtt_min, rtt_standing);
+
+        // rates in packets per second
+        let queueing_delay = (rtt_standing - rtt_min) / 1e6;
+        let mss = f64::from(self.mss);
+        let current_rate = (self.cwnd / mss) / (rtt_standing / 1e6);
+        let step = self.velocity * mss * f64::from(m.acked) / (self.delta * self.cwnd);
+        if queueing_delay <= 0.0 || current_rate <= 1.0 / (self.delta * queueing_delay) {
+            if self.direction == Direction::Down {
+                self.velocity = 1.0;
+            }
+            self.cwnd += step;
+        } else {
+            if self.direction == Direction::Up {
+                self.velocity = 1.0;
+            }
+            self.cwnd -= step;
+        }
+
+        let min_cwnd = 2.0 * mss;
+        if self.cwnd < min_cwnd {
+            self.cwnd = min_cwnd;
+        }
+
+        self.on_rtt_elapsed(now);
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        // default mode ignores loss; competitive mode halves 1/delta (MD in the AIMD on 1/delta)
+        if self.competitive {
+            self.delta = (self.delta * 2.0).min(self.default_delta);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.delta = self.default_delta;
+        self.velocity = 1.0;
+        self.same_direction_rtts = 0;
+        self.cwnd = self.init_cwnd;
+        self.rtt_start_cwnd = self.init_cwnd;
+    }
+
+    /// The previous algorithm's smoothed RTT stands in until Copa has its own.
+    fn import_state(&mut self, state: &FlowHandoff) {
+        self.set_cwnd(state.cwnd);
+        self.rtt_start_cwnd = self.cwnd;
+        if state.srtt > 0 {
+            self.srtt = f64::from(state.srtt);
+        }
+    }
+}
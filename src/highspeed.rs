@@ -0,0 +1,178 @@
+use clap::Arg;
+
+use ::{parse_arg, RemoteGenericCongAvoidAlg};
+use response_function::{ResponseFunction, ResponseFunctionFlow};
+
+/// Packet drop rate at `high_window`.
+const HIGH_P: f64 = 1e-7;
+/// Largest accepted `high_window`, in packets. The table is built by stepping through
+/// every window up to it.
+const MAX_HIGH_WINDOW: f64 = 1e6;
+
+/// HighSpeed TCP (RFC 3649).
+///
+/// Behaves like Reno up to `low_window` packets. Above it, a(w) and b(w) are looked up
+/// in a table generated from the RFC's response function, with one row per integer
+/// step of a(w) (as in the RFC's Appendix B).
+#[derive(Clone)]
+pub struct HighSpeed {
+    low_window: f64,
+    high_window: f64,
+    high_decrease: f64,
+    /// (w, a(w), b(w)), sorted by w.
+    table: Vec<(f64, f64, f64)>,
+}
+
+impl HighSpeed {
+    pub fn new(low_window: f64, high_window: f64, high_decrease: f64) -> Self {
+        let mut hs = HighSpeed {
+            low_window,
+            high_window,
+            high_decrease,
+            table: vec![],
+        };
+
+        hs.table = hs.make_table();
+        hs
+    }
+
+    fn log_fraction(&self, w: f64) -> f64 {
+        (w.ln() - self.low_window.ln()) / (self.high_window.ln() - self.low_window.ln())
+    }
+
+    fn b(&self, w: f64) -> f64 {
+        (self.high_decrease - 0.5) * self.log_fraction(w) + 0.5
+    }
+
+    fn p(&self, w: f64) -> f64 {
+        // Reno's drop rate at low_window, from w = sqrt(1.5 / p)
+        let low_p = 1.5 / (self.low_window * self.low_window);
+        (self.log_fraction(w) * (HIGH_P.ln() - low_p.ln()) + low_p.ln()).exp()
+    }
+
+    fn a(&self, w: f64) -> f64 {
+        let b = self.b(w);
+        w * w * self.p(w) * 2.0 * b / (2.0 - b)
+    }
+
+    fn make_table(&self) -> Vec<(f64, f64, f64)> {
+        let mut table = vec![(self.low_window, 1.0, 0.5)];
+        let mut w = self.low_window;
+        while w < self.high_window {
+            w += 1.0;
+            let a = self.a(w).round();
+            if a > table[table.len() - 1].1 {
+                table.push((w, a, self.b(w)));
+            }
+        }
+
+        table
+    }
+
+    fn lookup(&self, w: f64) -> (f64, f64) {
+        if w <= self.low_window {
+            return (1.0, 0.5);
+        }
+
+        let idx = match self.table.binary_search_by(|&(tw, _, _)| tw.partial_cmp(&w).unwrap()) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        let (_, a, b) = self.table[idx];
+        (a, b)
+    }
+}
+
+impl ResponseFunction for HighSpeed {
+    fn increase(&self, w: f64) -> f64 {
+        self.lookup(w).0
+    }
+
+    fn decrease(&self, w: f64) -> f64 {
+        self.lookup(w).1
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for HighSpeed {
+    type Flow = ResponseFunctionFlow<HighSpeed>;
+
+    fn name() -> &'static str {
+        "highspeed"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("highspeed_low_window")
+                .long("highspeed_low_window")
+                .help("Window, in packets, up to which HighSpeed TCP behaves like Reno")
+                .default_value("38"),
+            Arg::with_name("highspeed_high_window")
+                .long("highspeed_high_window")
+                .help("Window, in packets, at which the response function reaches high_decrease")
+                .default_value("83000"),
+            Arg::with_name("highspeed_high_decrease")
+                .long("highspeed_high_decrease")
+                .help("Multiplicative decrease factor at high_window")
+                .default_value("0.1"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        let low_window: f64 = parse_arg(&matches, "highspeed_low_window")?;
+        let high_window: f64 = parse_arg(&matches, "highspeed_high_window")?;
+        let high_decrease: f64 = parse_arg(&matches, "highspeed_high_decrease")?;
+        if !(0.0 < low_window && low_window < high_window) {
+            return Err(String::from(
+                "--highspeed_low_window must be positive and below --highspeed_high_window",
+            ));
+        }
+        if high_window > MAX_HIGH_WINDOW {
+            return Err(format!("--highspeed_high_window must be at most {}", MAX_HIGH_WINDOW));
+        }
+        if !(0.0 < high_decrease && high_decrease < 1.0) {
+            return Err(String::from("--highspeed_high_decrease must be between 0 and 1"));
+        }
+
+        Ok(HighSpeed::new(low_window, high_window, high_decrease))
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                _sock_id: u32) -> Self::Flow {
+        ResponseFunctionFlow::new(logger, init_cwnd, mss, self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HighSpeed;
+    use RemoteGenericCongAvoidAlg;
+
+    fn with_args(args: &[&str]) -> Result<HighSpeed, String> {
+        let matches = clap::App::new("highspeed")
+            .args(&HighSpeed::args())
+            .get_matches_from_safe(Some("highspeed").iter().chain(args))
+            .unwrap();
+        HighSpeed::with_args(matches)
+    }
+
+    #[test]
+    fn default_table_is_increasing() {
+        let hs = with_args(&[]).unwrap();
+        assert!(hs.table.windows(2).all(|r| r[0].0 < r[1].0 && r[0].1 < r[1].1));
+        assert_eq!(hs.lookup(1e9).1, hs.table[hs.table.len() - 1].2);
+    }
+
+    #[test]
+    fn bad_windows_are_rejected() {
+        assert!(with_args(&["--highspeed_low_window", "0"]).is_err());
+        assert!(with_args(&["--highspeed_low_window", "100", "--highspeed_high_window", "100"]).is_err());
+        assert!(with_args(&["--highspeed_high_window", "1e12"]).is_err());
+        assert!(with_args(&["--highspeed_low_window", "NaN"]).is_err());
+    }
+
+    #[test]
+    fn bad_decrease_is_rejected() {
+        assert!(with_args(&["--highspeed_high_decrease", "0"]).is_err());
+        assert!(with_args(&["--highspeed_high_decrease", "1"]).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use clap::Arg;
+use time::{self, Timespec};
+
+use ::{parse_arg, parse_opt_arg, RemoteGenericCongAvoidAlg, NetworkStatus};
+use GenericCongAvoidFlow;
+use GenericCongAvoidMeasurements;
+
+/// Below this normalized inflight, the controller is not reporting a load to react to
+/// (e.g. at flow start), and the multiplicative update would blow up.
+const MIN_NORMALIZED_INFLIGHT: f64 = 1e-3;
+
+/// HPCC, with the SDCCP controller standing in for in-network telemetry.
+///
+/// The normalized inflight of the bottleneck is estimated as
+/// `link_utilization + queue_length / bdp`. While it is below `eta` the window grows
+/// additively for up to `max_stage` RTTs; otherwise the reference window is scaled
+/// multiplicatively by `eta / U`. As in HPCC, the window never exceeds the line-rate
+/// BDP (`max_cwnd`, by default `bdp`).
+pub struct Hpcc {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    init_cwnd: f64,
+    cwnd: f64,
+
+    eta: f64,
+    max_stage: u32,
+    additive_increase: f64,
+    bdp: f64,
+    max_cwnd: Option<f64>,
+
+    reference_cwnd: f64,
+    inc_stage: u32,
+    last_reference_update: Timespec,
+    server_url: String,
+}
+
+impl Default for Hpcc {
+    fn default() -> Self {
+        Hpcc {
+            logger: None,
+            mss: 0,
+            init_cwnd: 0.0,
+            cwnd: 0.0,
+
+            eta: 0.95,
+            max_stage: 5,
+            additive_increase: 1.0,
+            bdp: 125_000.0,
+            max_cwnd: None,
+
+            reference_cwnd: 0.0,
+            inc_stage: 0,
+            last_reference_update: time::now().to_timespec(),
+            server_url: String::new(),
+        }
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Hpcc {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "hpcc"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("hpcc_eta")
+                .long("hpcc_eta")
+                .help("Target normalized inflight (utilization) of the bottleneck")
+                .default_value("0.95"),
+            Arg::with_name("hpcc_max_stage")
+                .long("hpcc_max_stage")
+                .help("Maximum number of consecutive additive increase stages before a multiplicative update")
+                .default_value("5"),
+            Arg::with_name("hpcc_wai")
+                .long("hpcc_wai")
+                .help("Additive increase step, in packets")
+                .default_value("1"),
+            Arg::with_name("hpcc_bdp")
+                .long("hpcc_bdp")
+                .help("Bottleneck bandwidth-delay product in bytes, used to normalize the reported queue length")
+                .default_value("125000"),
+            Arg::with_name("hpcc_max_cwnd")
+                .long("hpcc_max_cwnd")
+                .takes_value(true)
+                .help("Largest window in bytes, defaults to --hpcc_bdp"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Hpcc {
+            eta: parse_arg(&matches, "hpcc_eta")?,
+            max_stage: parse_arg(&matches, "hpcc_max_stage")?,
+            additive_increase: parse_arg(&matches, "hpcc_wai")?,
+            bdp: parse_arg(&matches, "hpcc_bdp")?,
+            max_cwnd: parse_opt_arg(&matches, "hpcc_max_cwnd")?,
+            ..Default::default()
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                sock_id: u32) -> Self::Flow {
+        Hpcc {
+            logger,
+            mss,
+            init_cwnd: f64::from(init_cwnd),
+            cwnd: f64::from(init_cwnd),
+
+            eta: self.eta,
+            max_stage: self.max_stage,
+            additive_increase: self.additive_increase,
+            bdp: self.bdp,
+            max_cwnd: self.max_cwnd,
+
+            reference_cwnd: f64::from(init_cwnd),
+            server_url: NetworkStatus::url(sock_id),
+            ..Default::default()
+        }
+    }
+}
+
+impl Hpcc {
+    /// Normalized inflight bytes at the bottleneck, from the controller's report.
+    fn normalized_inflight(&self, network_status: &NetworkStatus) -> f64 {
+        let utilization = f64::from(network_status.link_utilization.max(0.0));
+        let queue = f64::from(network_status.queue_length.max(0));
+        if self.bdp > 0.0 {
+            utilization + queue / self.bdp
+        } else {
+            utilization
+        }
+    }
+}
+
+impl GenericCongAvoidFlow for Hpcc {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+        self.reference_cwnd = self.cwnd;
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        self.cwnd += self.additive_increase * f64::from(self.mss) * (f64::from(m.acked) / self.cwnd);
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        self.cwnd /= 2.0;
+        if self.cwnd <= self.init_cwnd {
+            self.cwnd = self.init_cwnd;
+        }
+        self.reference_cwnd = self.cwnd;
+    }
+
+    fn reset(&mut self) {
+        self.inc_stage = 0;
+    }
+
+    fn adjust_cwnd(&mut self,
+                   network_status: &NetworkStatus,
+                   m: &GenericCongAvoidMeasurements)
+    {
+        let u = self.normalized_inflight(network_status);
+        let w_ai = self.additive_increase * f64::from(self.mss);
+
+        // the reference window (and the stage counter) only advance once per RTT
+        let now = time::now().to_timespec();
+        let new_rtt = (now - self.last_reference_update)
+            >= time::Duration::microseconds(i64::from(m.rtt));
+
+        let multiplicative = u >= MIN_NORMALIZED_INFLIGHT
+            && (u >= self.eta || self.inc_stage >= self.max_stage);
+        self.cwnd = if multiplicative {
+            self.reference_cwnd / (u / self.eta) + w_ai
+        } else {
+            self.reference_cwnd + w_ai
+        };
+
+        let max_cwnd = self.max_cwnd.unwrap_or(self.bdp);
+        if max_cwnd > 0.0 && self.cwnd > max_cwnd {
+            self.cwnd = max_cwnd;
+        }
+
+        let min_cwnd = f64::from(self.mss);
+        if self.cwnd < min_cwnd {
+            self.cwnd = min_cwnd;
+        }
+
+        if new_rtt {
+            if multiplicative {
+                self.inc_stage = 0;
+            } else {
+                self.inc_stage += 1;
+            }
+            self.reference_cwnd = self.cwnd;
+            self.last_reference_update = now;
+        }
+
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "hpcc";
+                "normalized_inflight" => u,
+                "inc_stage" => self.inc_stage,
+                "cwnd (pkts)" => self.cwnd as u32 / self.mss,
+            );
+        }
+    }
+
+    fn update_network_status(&mut self) -> NetworkStatus {
+        NetworkStatus::fetch(&self.server_url)
+    }
+}
@@ -0,0 +1,179 @@
+use time::{self, Timespec};
+
+const MIN_RTT_THRESH_US: u32 = 4_000;
+const MAX_RTT_THRESH_US: u32 = 16_000;
+const MIN_RTT_DIVISOR: u32 = 8;
+const N_RTT_SAMPLE: u32 = 8;
+const CSS_ROUNDS: u32 = 5;
+
+/// Slow start grows cwnd this many times slower in conservative slow start.
+pub const CSS_GROWTH_DIVISOR: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HyStartPhase {
+    SlowStart,
+    /// The RTT rose; probe more gently in case the increase was spurious.
+    Conservative,
+    /// Slow start is over.
+    Done,
+}
+
+/// HyStart++ (RFC 9406) slow start exit.
+///
+/// Without per-packet sequence numbers, a round ends once a smoothed RTT has passed
+/// since it began. Each report contributes its minimum RTT, and counts one RTT sample
+/// per acked packet.
+#[derive(Debug, Clone)]
+pub struct HyStart {
+    phase: HyStartPhase,
+    round_start: Timespec,
+    last_round_min_rtt: Option<u32>,
+    current_round_min_rtt: Option<u32>,
+    rtt_samples: u32,
+    css_baseline_min_rtt: u32,
+    css_rounds: u32,
+}
+
+impl Default for HyStart {
+    fn default() -> Self {
+        HyStart {
+            phase: HyStartPhase::SlowStart,
+            round_start: time::now().to_timespec(),
+            last_round_min_rtt: None,
+            current_round_min_rtt: None,
+            rtt_samples: 0,
+            css_baseline_min_rtt: 0,
+            css_rounds: 0,
+        }
+    }
+}
+
+impl HyStart {
+    pub fn phase(&self) -> HyStartPhase {
+        self.phase
+    }
+
+    /// Account for a report with `samples` RTT samples, the smallest of which is
+    /// `rtt_min` microseconds.
+    pub fn on_report(&mut self, srtt: u32, rtt_min: u32, samples: u32) -> HyStartPhase {
+        self.on_report_at(time::now().to_timespec(), srtt, rtt_min, samples)
+    }
+
+    fn on_report_at(&mut self, now: Timespec, srtt: u32, rtt_min: u32, samples: u32) -> HyStartPhase {
+        if self.phase == HyStartPhase::Done || rtt_min == 0 {
+            return self.phase;
+        }
+
+        if now - self.round_start >= time::Duration::microseconds(i64::from(srtt)) {
+            self.start_round(now);
+            if self.phase == HyStartPhase::Done {
+                return self.phase;
+            }
+        }
+
+        self.current_round_min_rtt = Some(match self.current_round_min_rtt {
+            Some(min) => min.min(rtt_min),
+            None => rtt_min,
+        });
+        self.rtt_samples += samples;
+        if self.rtt_samples < N_RTT_SAMPLE {
+            return self.phase;
+        }
+
+        let current = self.current_round_min_rtt.unwrap();
+        match (self.phase, self.last_round_min_rtt) {
+            (HyStartPhase::SlowStart, Some(last)) => {
+                let thresh = (last / MIN_RTT_DIVISOR).clamp(MIN_RTT_THRESH_US, MAX_RTT_THRESH_US);
+                if current >= last + thresh {
+                    self.css_baseline_min_rtt = current;
+                    self.css_rounds = 0;
+                    self.phase = HyStartPhase::Conservative;
+                }
+            }
+            (HyStartPhase::Conservative, _) if current < self.css_baseline_min_rtt => {
+                // the RTT increase was spurious
+                self.phase = HyStartPhase::SlowStart;
+            }
+            _ => {}
+        }
+
+        self.phase
+    }
+
+    fn start_round(&mut self, now: Timespec) {
+        self.round_start = now;
+        self.last_round_min_rtt = self.current_round_min_rtt.take();
+        self.rtt_samples = 0;
+
+        if self.phase == HyStartPhase::Conservative {
+            self.css_rounds += 1;
+            if self.css_rounds >= CSS_ROUNDS {
+                self.phase = HyStartPhase::Done;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, Timespec};
+
+    use super::{HyStart, HyStartPhase, CSS_ROUNDS, N_RTT_SAMPLE};
+
+    const SRTT: u32 = 20_000;
+
+    /// Feeds HyStart++ one round at a time.
+    struct Rounds {
+        hystart: HyStart,
+        now: Timespec,
+    }
+
+    impl Rounds {
+        fn new() -> Self {
+            let hystart = HyStart::default();
+            let now = hystart.round_start;
+            Rounds { hystart, now }
+        }
+
+        /// A report within the current round.
+        fn report(&mut self, rtt_min: u32) -> HyStartPhase {
+            self.hystart.on_report_at(self.now, SRTT, rtt_min, N_RTT_SAMPLE)
+        }
+
+        /// The first report of the next round.
+        fn next_round(&mut self, rtt_min: u32) -> HyStartPhase {
+            self.now = self.now + Duration::microseconds(i64::from(SRTT));
+            self.report(rtt_min)
+        }
+    }
+
+    #[test]
+    fn rtt_increase_enters_conservative_slow_start() {
+        let mut r = Rounds::new();
+        assert_eq!(r.report(10_000), HyStartPhase::SlowStart);
+        // below the 4ms minimum threshold
+        assert_eq!(r.next_round(13_000), HyStartPhase::SlowStart);
+        assert_eq!(r.next_round(17_500), HyStartPhase::Conservative);
+    }
+
+    #[test]
+    fn spurious_increase_resumes_slow_start() {
+        let mut r = Rounds::new();
+        r.report(10_000);
+        assert_eq!(r.next_round(15_000), HyStartPhase::Conservative);
+        assert_eq!(r.report(14_000), HyStartPhase::SlowStart);
+    }
+
+    #[test]
+    fn conservative_slow_start_ends_after_css_rounds() {
+        let mut r = Rounds::new();
+        r.report(10_000);
+        assert_eq!(r.next_round(15_000), HyStartPhase::Conservative);
+        for _ in 1..CSS_ROUNDS {
+            assert_eq!(r.next_round(15_000), HyStartPhase::Conservative);
+        }
+        assert_eq!(r.next_round(15_000), HyStartPhase::Done);
+        assert_eq!(r.hystart.phase(), HyStartPhase::Done);
+        assert_eq!(r.next_round(1_000), HyStartPhase::Done);
+    }
+}
@@ -0,0 +1,204 @@
+use clap::Arg;
+use time::{self, Timespec};
+
+use ::{parse_arg, FlowHandoff, RemoteGenericCongAvoidAlg, GenericCongAvoidFlow,
+       GenericCongAvoidMeasurements};
+
+const ALPHA_MIN: f64 = 0.3;
+const ALPHA_MAX: f64 = 10.0;
+const BETA_MIN: f64 = 0.125;
+const BETA_MAX: f64 = 0.5;
+
+/// TCP-Illinois: loss-based AIMD whose alpha and beta depend on the queueing delay.
+///
+/// Once per RTT, the average queueing delay `da` (average RTT minus base RTT) is compared
+/// with the maximum queueing delay `dm` seen so far. Small `da` gives a large alpha and a
+/// small beta; as `da` approaches `dm`, alpha falls towards `ALPHA_MIN` and beta rises
+/// towards `BETA_MAX`. Below `win_thresh` packets, Illinois behaves like Reno.
+pub struct Illinois {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    init_cwnd: f64,
+    cwnd: f64,
+
+    win_thresh: f64,
+
+    alpha: f64,
+    beta: f64,
+    base_rtt: u32,
+    max_rtt: u32,
+    rtt_sum: u64,
+    rtt_count: u32,
+    last_update: Timespec,
+}
+
+impl Default for Illinois {
+    fn default() -> Self {
+        Illinois {
+            logger: None,
+            mss: 0,
+            init_cwnd: 0.0,
+            cwnd: 0.0,
+
+            win_thresh: 15.0,
+
+            alpha: 1.0,
+            beta: BETA_MAX,
+            base_rtt: 0,
+            max_rtt: 0,
+            rtt_sum: 0,
+            rtt_count: 0,
+            last_update: time::now().to_timespec(),
+        }
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Illinois {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "illinois"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("illinois_win_thresh")
+                .long("illinois_win_thresh")
+                .help("Window, in packets, up to which Illinois behaves like Reno")
+                .default_value("15"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Illinois {
+            win_thresh: parse_arg(&matches, "illinois_win_thresh")?,
+            ..Default::default()
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                _sock_id: u32) -> Self::Flow {
+        Illinois {
+            logger,
+            mss,
+            init_cwnd: f64::from(init_cwnd),
+            cwnd: f64::from(init_cwnd),
+            win_thresh: self.win_thresh,
+            ..Default::default()
+        }
+    }
+}
+
+impl Illinois {
+    fn update_params(&mut self, rtt: u32) {
+        if self.base_rtt == 0 || rtt < self.base_rtt {
+            self.base_rtt = rtt;
+        }
+
+        if rtt > self.max_rtt {
+            self.max_rtt = rtt;
+        }
+
+        self.rtt_sum += u64::from(rtt);
+        self.rtt_count += 1;
+
+        let now = time::now().to_timespec();
+        if (now - self.last_update) < time::Duration::microseconds(i64::from(rtt)) {
+            return;
+        }
+
+        let avg_rtt = self.rtt_sum as f64 / f64::from(self.rtt_count);
+        self.rtt_sum = 0;
+        self.rtt_count = 0;
+        self.last_update = now;
+
+        if self.cwnd / f64::from(self.mss) < self.win_thresh {
+            self.alpha = 1.0;
+            self.beta = BETA_MAX;
+            return;
+        }
+
+        let da = avg_rtt - f64::from(self.base_rtt);
+        let dm = f64::from(self.max_rtt - self.base_rtt);
+        self.alpha = Illinois::alpha(da, dm);
+        self.beta = Illinois::beta(da, dm);
+
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "illinois";
+                "avg_delay" => da,
+                "max_delay" => dm,
+                "alpha" => self.alpha,
+                "beta" => self.beta,
+            );
+        }
+    }
+
+    fn alpha(da: f64, dm: f64) -> f64 {
+        let d1 = dm / 100.0;
+        if da <= d1 {
+            return ALPHA_MAX;
+        }
+
+        let k1 = (dm - d1) * ALPHA_MIN * ALPHA_MAX / (ALPHA_MAX - ALPHA_MIN);
+        let k2 = (dm - d1) * ALPHA_MIN / (ALPHA_MAX - ALPHA_MIN) - d1;
+        k1 / (k2 + da)
+    }
+
+    fn beta(da: f64, dm: f64) -> f64 {
+        let d2 = dm / 10.0;
+        let d3 = 8.0 * dm / 10.0;
+        if da <= d2 {
+            BETA_MIN
+        } else if da >= d3 {
+            BETA_MAX
+        } else {
+            (BETA_MIN * d3 - BETA_MAX * d2 + (BETA_MAX - BETA_MIN) * da) / (d3 - d2)
+        }
+    }
+}
+
+impl GenericCongAvoidFlow for Illinois {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn use_remote(&self) -> bool {
+        false
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        if m.rtt > 0 {
+            self.update_params(m.rtt);
+        }
+
+        // increase cwnd by alpha / cwnd per packet
+        self.cwnd += self.alpha * f64::from(self.mss) * (f64::from(m.acked) / self.cwnd);
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        self.cwnd *= 1.0 - self.beta;
+        if self.cwnd <= self.init_cwnd {
+            self.cwnd = self.init_cwnd;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.alpha = 1.0;
+        self.beta = BETA_MAX;
+        self.rtt_sum = 0;
+        self.rtt_count = 0;
+    }
+
+    /// The previous algorithm's smoothed RTT bounds the base RTT until a smaller sample
+    /// arrives.
+    fn import_state(&mut self, state: &FlowHandoff) {
+        self.set_cwnd(state.cwnd);
+        if state.srtt > 0 {
+            self.base_rtt = state.srtt;
+        }
+    }
+}
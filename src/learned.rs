@@ -0,0 +1,299 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+
+use clap::Arg;
+use serde_json;
+
+use ::{parse_arg, RemoteGenericCongAvoidAlg, NetworkStatus, StatusChange};
+use GenericCongAvoidFlow;
+use GenericCongAvoidMeasurements;
+
+/// Number of features extracted from each report.
+pub const NUM_FEATURES: usize = 8;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Activation {
+    Linear,
+    Relu,
+    Tanh,
+    Sigmoid,
+}
+
+impl Activation {
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            Activation::Linear => x,
+            Activation::Relu => x.max(0.0),
+            Activation::Tanh => x.tanh(),
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+        }
+    }
+}
+
+/// A fully connected layer: `activation(weights * input + bias)`.
+/// `weights` has one row per output.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Layer {
+    pub weights: Vec<Vec<f64>>,
+    pub bias: Vec<f64>,
+    pub activation: Activation,
+}
+
+fn default_history() -> usize {
+    1
+}
+
+/// A small MLP policy, loaded from JSON:
+///
+/// ```json
+/// {
+///   "history": 4,
+///   "layers": [
+///     { "weights": [[...], ...], "bias": [...], "activation": "relu" },
+///     { "weights": [[...]], "bias": [0.0], "activation": "linear" }
+///   ]
+/// }
+/// ```
+///
+/// The input is the feature vectors of the last `history` reports, newest first, so the
+/// first layer must have `history * NUM_FEATURES` columns. The last layer must have a
+/// single output, the cwnd multiplier.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Policy {
+    #[serde(default = "default_history")]
+    pub history: usize,
+    pub layers: Vec<Layer>,
+}
+
+impl Policy {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        let policy: Policy = serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| format!("cannot parse {}: {}", path, e))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let mut width = self.history * NUM_FEATURES;
+        for (i, layer) in self.layers.iter().enumerate() {
+            if layer.weights.len() != layer.bias.len() {
+                return Err(format!("layer {}: {} weight rows but {} biases",
+                                   i, layer.weights.len(), layer.bias.len()));
+            }
+
+            if let Some(row) = layer.weights.iter().find(|row| row.len() != width) {
+                return Err(format!("layer {}: expected {} inputs, got {}", i, width, row.len()));
+            }
+
+            width = layer.bias.len();
+        }
+
+        if width != 1 {
+            return Err(format!("policy must have a single output, got {}", width));
+        }
+
+        Ok(())
+    }
+
+    pub fn evaluate(&self, input: &[f64]) -> f64 {
+        let mut x = input.to_vec();
+        for layer in &self.layers {
+            x = layer.weights.iter()
+                .zip(layer.bias.iter())
+                .map(|(row, b)| {
+                    let sum: f64 = row.iter().zip(x.iter()).map(|(w, v)| w * v).sum();
+                    layer.activation.apply(sum + b)
+                })
+                .collect();
+        }
+
+        x[0]
+    }
+}
+
+/// Scale cwnd by the output of a learned policy on every controller update.
+///
+/// Each report is featurized as (acked / cwnd, sacked / cwnd, loss / cwnd, timeout,
+/// rtt / min rtt, inflight / cwnd, link utilization, queue length / cwnd), all in
+/// packets where applicable. The policy output is clamped to
+/// `[min_multiplier, max_multiplier]`. Reports between controller updates leave cwnd
+/// unchanged.
+#[derive(Clone)]
+pub struct Learned {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    init_cwnd: f64,
+    cwnd: f64,
+
+    policy: Policy,
+    min_multiplier: f64,
+    max_multiplier: f64,
+
+    min_rtt: u32,
+    history: VecDeque<[f64; NUM_FEATURES]>,
+    status_change: StatusChange,
+    server_url: String,
+}
+
+impl RemoteGenericCongAvoidAlg for Learned {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "learned"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("learned_policy")
+                .long("learned_policy")
+                .takes_value(true)
+                .help("Path to the JSON policy file"),
+            Arg::with_name("learned_min_multiplier")
+                .long("learned_min_multiplier")
+                .help("Smallest cwnd multiplier the policy may output")
+                .default_value("0.5"),
+            Arg::with_name("learned_max_multiplier")
+                .long("learned_max_multiplier")
+                .help("Largest cwnd multiplier the policy may output")
+                .default_value("2.0"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        let path = matches.value_of("learned_policy")
+            .ok_or("--learned_policy is required")?;
+        let policy = Policy::load(path)
+            .map_err(|e| format!("invalid policy: {}", e))?;
+
+        Ok(Learned {
+            logger: None,
+            mss: 0,
+            init_cwnd: 0.0,
+            cwnd: 0.0,
+
+            policy,
+            min_multiplier: parse_arg(&matches, "learned_min_multiplier")?,
+            max_multiplier: parse_arg(&matches, "learned_max_multiplier")?,
+
+            min_rtt: 0,
+            history: VecDeque::new(),
+            status_change: StatusChange::default(),
+            server_url: String::new(),
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                sock_id: u32) -> Self::Flow {
+        Learned {
+            logger,
+            mss,
+            init_cwnd: f64::from(init_cwnd),
+            cwnd: f64::from(init_cwnd),
+            server_url: NetworkStatus::url(sock_id),
+            ..self.clone()
+        }
+    }
+}
+
+impl Learned {
+    fn observe_rtt(&mut self, m: &GenericCongAvoidMeasurements) {
+        if m.rtt_min > 0 && (self.min_rtt == 0 || m.rtt_min < self.min_rtt) {
+            self.min_rtt = m.rtt_min;
+        }
+    }
+
+    fn featurize(&self, network_status: &NetworkStatus,
+                 m: &GenericCongAvoidMeasurements) -> [f64; NUM_FEATURES] {
+        let mss = f64::from(self.mss);
+        let cwnd_pkts = self.cwnd / mss;
+        let rtt_ratio = if self.min_rtt > 0 {
+            f64::from(m.rtt) / f64::from(self.min_rtt)
+        } else {
+            1.0
+        };
+
+        [
+            f64::from(m.acked) / self.cwnd,
+            f64::from(m.sacked) / cwnd_pkts,
+            f64::from(m.loss) / cwnd_pkts,
+            if m.was_timeout { 1.0 } else { 0.0 },
+            rtt_ratio,
+            f64::from(m.inflight) / cwnd_pkts,
+            f64::from(network_status.link_utilization),
+            f64::from(network_status.queue_length.max(0)) / self.cwnd,
+        ]
+    }
+
+    fn input(&self) -> Vec<f64> {
+        let mut input = vec![0.0; self.policy.history * NUM_FEATURES];
+        for (i, features) in self.history.iter().enumerate() {
+            input[i * NUM_FEATURES..(i + 1) * NUM_FEATURES].copy_from_slice(features);
+        }
+
+        input
+    }
+}
+
+impl GenericCongAvoidFlow for Learned {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        // increase cwnd by 1 / cwnd per packet
+        self.cwnd += f64::from(self.mss) * (f64::from(m.acked) / self.cwnd);
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        self.cwnd /= 2.0;
+        if self.cwnd <= self.init_cwnd {
+            self.cwnd = self.init_cwnd;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    fn adjust_cwnd(&mut self,
+                   network_status: &NetworkStatus,
+                   m: &GenericCongAvoidMeasurements)
+    {
+        self.observe_rtt(m);
+        if !self.status_change.is_new(network_status) {
+            return;
+        }
+
+        let features = self.featurize(network_status, m);
+        self.history.push_front(features);
+        self.history.truncate(self.policy.history);
+
+        let multiplier = self.policy.evaluate(&self.input())
+            .max(self.min_multiplier)
+            .min(self.max_multiplier);
+        self.cwnd *= multiplier;
+
+        let min_cwnd = 2.0 * f64::from(self.mss);
+        if self.cwnd < min_cwnd {
+            self.cwnd = min_cwnd;
+        }
+
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "learned policy";
+                "multiplier" => multiplier,
+                "cwnd (pkts)" => self.cwnd as u32 / self.mss,
+            );
+        }
+    }
+
+    fn update_network_status(&mut self) -> NetworkStatus {
+        NetworkStatus::fetch(&self.server_url)
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::VecDeque;
+
+use clap::Arg;
+use time::{self, Timespec};
+
+use ::{parse_arg, parse_opt_arg, RemoteGenericCongAvoidAlg, NetworkStatus};
+use GenericCongAvoidFlow;
+use GenericCongAvoidMeasurements;
+
+/// Number of recent delay samples the current delay is filtered (min) over.
+const CURRENT_FILTER: usize = 4;
+/// Minimum cwnd, in packets.
+const MIN_CWND: f64 = 2.0;
+/// cwnd may exceed the bytes in flight by at most this many packets.
+const ALLOWED_INCREASE: f64 = 1.0;
+
+/// LEDBAT (RFC 6817), a scavenger that keeps the queueing delay it induces near `target`.
+///
+/// The report RTT stands in for the one-way delay. The base delay is the minimum over
+/// `base_history` one-minute buckets. When a utilization threshold is configured, the
+/// SDCCP controller is also polled, at most once per RTT, and the flow backs off further
+/// as the bottleneck's utilization approaches 1.
+pub struct Ledbat {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    cwnd: f64,
+
+    target: f64,
+    gain: f64,
+    base_history: usize,
+    yield_utilization: Option<f32>,
+
+    base_delays: VecDeque<u32>,
+    last_rollover: Timespec,
+    current_delays: VecDeque<u32>,
+    /// The utilization last reported by the controller, and when it was polled.
+    utilization: Option<f32>,
+    last_poll: Option<Timespec>,
+    server_url: String,
+}
+
+impl Default for Ledbat {
+    fn default() -> Self {
+        Ledbat {
+            logger: None,
+            mss: 0,
+            cwnd: 0.0,
+
+            target: 100_000.0,
+            gain: 1.0,
+            base_history: 10,
+            yield_utilization: None,
+
+            base_delays: VecDeque::new(),
+            last_rollover: time::now().to_timespec(),
+            current_delays: VecDeque::new(),
+            utilization: None,
+            last_poll: None,
+            server_url: String::new(),
+        }
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Ledbat {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "ledbat"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("ledbat_target_ms")
+                .long("ledbat_target_ms")
+                .help("Target queueing delay, in milliseconds")
+                .default_value("100"),
+            Arg::with_name("ledbat_gain")
+                .long("ledbat_gain")
+                .help("cwnd gain per RTT when the queueing delay is far from the target")
+                .default_value("1"),
+            Arg::with_name("ledbat_base_history")
+                .long("ledbat_base_history")
+                .help("Number of one-minute intervals over which the base delay is tracked")
+                .default_value("10"),
+            Arg::with_name("ledbat_yield_utilization")
+                .long("ledbat_yield_utilization")
+                .takes_value(true)
+                .help("Poll the SDCCP controller and yield further once the link utilization exceeds this value"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Ledbat {
+            target: parse_arg::<f64>(&matches, "ledbat_target_ms")? * 1000.0,
+            gain: parse_arg(&matches, "ledbat_gain")?,
+            base_history: parse_arg(&matches, "ledbat_base_history")?,
+            yield_utilization: parse_opt_arg(&matches, "ledbat_yield_utilization")?,
+            ..Default::default()
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                sock_id: u32) -> Self::Flow {
+        Ledbat {
+            logger,
+            mss,
+            cwnd: f64::from(init_cwnd),
+
+            target: self.target,
+            gain: self.gain,
+            base_history: self.base_history,
+            yield_utilization: self.yield_utilization,
+
+            server_url: NetworkStatus::url(sock_id),
+            ..Default::default()
+        }
+    }
+}
+
+impl Ledbat {
+    fn update_base_delay(&mut self, delay: u32) {
+        let now = time::now().to_timespec();
+        if self.base_delays.is_empty() || (now - self.last_rollover) > time::Duration::minutes(1) {
+            self.last_rollover = now;
+            self.base_delays.push_back(delay);
+            while self.base_delays.len() > self.base_history.max(1) {
+                self.base_delays.pop_front();
+            }
+        } else if let Some(tail) = self.base_delays.back_mut() {
+            if delay < *tail {
+                *tail = delay;
+            }
+        }
+    }
+
+    fn update_current_delay(&mut self, delay: u32) {
+        self.current_delays.push_back(delay);
+        while self.current_delays.len() > CURRENT_FILTER {
+            self.current_delays.pop_front();
+        }
+    }
+
+    fn queueing_delay(&self) -> f64 {
+        let current = self.current_delays.iter().min().cloned().unwrap_or(0);
+        let base = self.base_delays.iter().min().cloned().unwrap_or(current);
+        f64::from(current.saturating_sub(base))
+    }
+
+    /// Poll the controller if an RTT has passed since the last poll. A failed poll keeps
+    /// the last utilization.
+    fn poll_utilization(&mut self, rtt: u32) {
+        let now = time::now().to_timespec();
+        if self.last_poll.is_some_and(|t| now - t < time::Duration::microseconds(i64::from(rtt))) {
+            return;
+        }
+
+        self.last_poll = Some(now);
+        match NetworkStatus::try_fetch(&self.server_url) {
+            Ok(status) => self.utilization = Some(status.link_utilization),
+            Err(e) => {
+                if let Some(log) = self.logger.as_ref() {
+                    warn!(log, "controller status unavailable"; "err" => e);
+                }
+            }
+        }
+    }
+
+    /// How far beyond `yield_utilization` the bottleneck is, scaled to [0, 1].
+    fn utilization_excess(&mut self, rtt: u32) -> f64 {
+        let threshold = match self.yield_utilization {
+            Some(t) if t < 1.0 => t,
+            _ => return 0.0,
+        };
+
+        self.poll_utilization(rtt);
+        let utilization = match self.utilization {
+            Some(u) => u,
+            None => return 0.0,
+        };
+        f64::from(((utilization - threshold) / (1.0 - threshold)).clamp(0.0, 1.0))
+    }
+}
+
+impl GenericCongAvoidFlow for Ledbat {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn use_remote(&self) -> bool {
+        false
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        if m.rtt == 0 {
+            return;
+        }
+
+        self.update_base_delay(m.rtt_min);
+        self.update_current_delay(m.rtt_min);
+
+        let queueing_delay = self.queueing_delay();
+        let off_target = (self.target - queueing_delay) / self.target - self.utilization_excess(m.rtt);
+
+        let mss = f64::from(self.mss);
+        self.cwnd += self.gain * off_target * f64::from(m.acked) * mss / self.cwnd;
+
+        let max_allowed_cwnd = f64::from(m.inflight) * mss + ALLOWED_INCREASE * mss;
+        if self.cwnd > max_allowed_cwnd {
+            self.cwnd = max_allowed_cwnd;
+        }
+
+        if self.cwnd < MIN_CWND * mss {
+            self.cwnd = MIN_CWND * mss;
+        }
+
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "ledbat";
+                "queueing_delay" => queueing_delay,
+                "off_target" => off_target,
+                "cwnd (pkts)" => self.cwnd as u32 / self.mss,
+            );
+        }
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        let min_cwnd = MIN_CWND * f64::from(self.mss);
+        self.cwnd = (self.cwnd / 2.0).max(min_cwnd);
+    }
+
+    fn reset(&mut self) {
+        self.current_delays.clear();
+    }
+}
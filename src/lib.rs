@@ -6,32 +6,219 @@ extern crate portus;
 #[macro_use]
 extern crate serde_derive;
 extern crate reqwest;
+extern crate serde_json;
 
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
 use portus::ipc::Ipc;
 use portus::lang::Scope;
 use portus::{CongAlg, Datapath, DatapathInfo, DatapathTrait, Report};
 
+pub mod aimd;
+pub mod bic;
+pub mod copa;
+pub mod highspeed;
+pub mod hpcc;
+pub mod hystart;
+pub mod illinois;
+pub mod learned;
+pub mod ledbat;
+pub mod pid;
+pub mod pid_controller;
+pub mod policy;
+pub mod program;
+pub mod queue;
+pub mod registry;
 pub mod reno;
+pub mod response_function;
+pub mod scalable;
+pub mod timely;
+pub mod utilization;
+pub mod westwood;
 
 mod bin_helper;
 pub use bin_helper::{make_args, start};
 
+use hystart::{HyStart, HyStartPhase, CSS_GROWTH_DIVISOR};
+use program::{ProgramSpec, Trigger};
+pub use program::Field;
+
+/// Parse the value of the argument `name`, which must be present.
+pub fn parse_arg<T: FromStr>(matches: &clap::ArgMatches, name: &str) -> Result<T, String>
+where
+    T::Err: fmt::Display,
+{
+    let value = matches.value_of(name)
+        .ok_or_else(|| format!("--{} is required", name))?;
+    value.parse()
+        .map_err(|e| format!("invalid value {:?} for --{}: {}", value, name, e))
+}
+
+/// Parse the value of the argument `name`, if it was given.
+pub fn parse_opt_arg<T: FromStr>(matches: &clap::ArgMatches, name: &str) -> Result<Option<T>, String>
+where
+    T::Err: fmt::Display,
+{
+    match matches.value_of(name) {
+        Some(_) => parse_arg(matches, name).map(Some),
+        None => Ok(None),
+    }
+}
+
 pub const DEFAULT_SS_THRESH: u32 = 0x7fff_ffff;
+pub const REST_ADDR: &str = "http://127.0.0.1:8080/get_user_link_utilization";
+/// After the first guardrail clamp of a flow, only every this many are logged.
+const GUARDRAIL_WARN_EVERY: u64 = 100;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default, Clone)]
 pub struct NetworkStatus {
     pub link_utilization: f32,
     pub queue_length: i32,
+    /// Number of flows sharing the bottleneck, if the controller reports it.
+    #[serde(default)]
+    pub flow_count: u32,
+    /// Algorithm the controller asks this flow to switch to, if any.
+    #[serde(default)]
+    pub alg: Option<String>,
+    /// When the controller computed this status, in milliseconds. Successive
+    /// timestamps reveal the controller's update period.
+    #[serde(default)]
+    pub timestamp_ms: Option<u64>,
+}
+
+impl NetworkStatus {
+    /// The controller endpoint reporting the bottleneck status for `sock_id`.
+    pub fn url(sock_id: u32) -> String {
+        format!("{}/{}", REST_ADDR, sock_id)
+    }
+
+    /// Query the SDCCP controller.
+    pub fn fetch(url: &str) -> NetworkStatus {
+        NetworkStatus::try_fetch(url).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Query the SDCCP controller, failing if it is unreachable or its response invalid.
+    pub fn try_fetch(url: &str) -> Result<NetworkStatus, String> {
+        let mut response = reqwest::get(url)
+            .map_err(|e| format!("Failed to get response from url ({:?}): {}", url, e))?;
+
+        response.json()
+            .map_err(|e| format!("Failed to parse the response {:?}: {}", response, e))
+    }
+}
+
+/// Tells new controller updates apart from repeats of the last one, by
+/// `NetworkStatus::timestamp_ms` if the controller sends it, or else by the reported values.
+#[derive(Debug, Clone, Default)]
+pub struct StatusChange {
+    last: Option<(Option<u64>, f32, i32)>,
+}
+
+impl StatusChange {
+    /// Whether `status` differs from the status last passed in.
+    pub fn is_new(&mut self, status: &NetworkStatus) -> bool {
+        let key = match status.timestamp_ms {
+            Some(ts) => (Some(ts), 0.0, 0),
+            None => (None, status.link_utilization, status.queue_length),
+        };
+        if self.last == Some(key) {
+            return false;
+        }
+
+        self.last = Some(key);
+        true
+    }
 }
 
+/// State carried over when a flow switches algorithms mid-flow, so the new algorithm
+/// continues from where the old one left off. Slow start itself belongs to the `Flow`
+/// and is unaffected by a switch.
+#[derive(Debug, Clone, Copy)]
+pub struct FlowHandoff {
+    pub cwnd: u32,
+    /// The flow's slow start threshold: `--ss_thresh` until the first loss or slow start
+    /// exit, and the cwnd after it from then on.
+    pub ss_thresh: u32,
+    /// Smoothed RTT, in microseconds.
+    pub srtt: u32,
+}
+
+#[derive(Default)]
 pub struct GenericCongAvoidMeasurements {
     pub acked: u32,
     pub was_timeout: bool,
     pub sacked: u32,
     pub loss: u32,
     pub rtt: u32,
+    /// Minimum, maximum and EWMA of the RTT samples since the last report, in
+    /// microseconds. `rtt` is only the latest sample.
+    pub rtt_min: u32,
+    pub rtt_max: u32,
+    pub rtt_ewma: u32,
     pub inflight: u32,
+    /// Bytes newly delivered (acked or sacked) since the last report.
+    pub delivered: u32,
+    /// Bytes delivered over the lifetime of the flow.
+    pub delivered_total: u64,
+    /// Maximum delivery and send rates since the last report, in bytes per second.
+    pub rate_incoming: u64,
+    pub rate_outgoing: u64,
+    /// The fields declared in `RemoteGenericCongAvoidAlg::extra_fields`.
+    pub extra: MeasurementExtensions,
+}
+
+impl GenericCongAvoidMeasurements {
+    /// Build the measurements from the `Report` fields of a program, keyed by field name.
+    /// Whatever is left after the standard and slow start fields is `extra`.
+    fn from_fields(mut fields: HashMap<String, u64>, delivered_total: u64) -> Self {
+        for f in slow_start_fields() {
+            fields.remove(&f.name);
+        }
+        let mut take = |name: &str| fields.remove(name).unwrap();
+        let rtt = take("rtt") as u32;
+        let delivered = take("delivered");
+        // rtt_min keeps its initial value if there were no samples
+        let rtt_min = match take("rtt_min") as u32 {
+            u32::MAX => rtt,
+            min => min,
+        };
+        GenericCongAvoidMeasurements {
+            acked: take("acked") as u32,
+            was_timeout: take("timeout") == 1,
+            sacked: take("sacked") as u32,
+            loss: take("loss") as u32,
+            rtt,
+            rtt_min,
+            rtt_max: take("rtt_max") as u32,
+            rtt_ewma: take("rtt_ewma") as u32,
+            inflight: take("inflight") as u32,
+            delivered: delivered as u32,
+            delivered_total: delivered_total + delivered,
+            rate_incoming: take("rate_incoming"),
+            rate_outgoing: take("rate_outgoing"),
+            extra: MeasurementExtensions(fields),
+        }
+    }
+}
+
+/// Values of the extra `Report` fields an algorithm declared, keyed by field name.
+#[derive(Debug, Clone, Default)]
+pub struct MeasurementExtensions(HashMap<String, u64>);
+
+impl MeasurementExtensions {
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.0.get(name).cloned()
+    }
+
+    pub fn get_u32(&self, name: &str) -> Option<u32> {
+        self.get(name).map(|v| v as u32)
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name).map(|v| v == 1)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -39,12 +226,88 @@ pub enum GenericCongAvoidConfigReport {
     Ack,
     Rtt,
     Interval(time::Duration),
+    /// Report once per SDCCP controller epoch. Without a configured period, report
+    /// every RTT until the period is learned from `NetworkStatus::timestamp_ms`. Only
+    /// controller-driven (`use_remote`) algorithms fetch statuses, so others stay on
+    /// per-RTT reports unless the period is configured.
+    ControllerEpoch(Option<time::Duration>),
+    /// Report every `min` around congestion events (CCP slow start and its exit, loss,
+    /// timeout, cwnd reduction), doubling the interval on each uneventful report up to `max`.
+    Adaptive { min: time::Duration, max: time::Duration },
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum GenericCongAvoidConfigSS {
     Datapath,
     Ccp,
+    /// No slow start: the algorithm controls cwnd from the first report.
+    Off,
+}
+
+/// Why slow start ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlowStartExit {
+    /// A loss or timeout.
+    Loss,
+    /// cwnd reached ss_thresh.
+    Threshold,
+    /// RTT samples rose above the slow start's minimum RTT (HyStart).
+    Delay,
+    /// The SDCCP controller reported a busy bottleneck.
+    Controller,
+}
+
+/// End slow start once the SDCCP controller reports a link utilization of at least
+/// `utilization`, or a queue longer than `queue` bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct ControllerExit {
+    pub utilization: f32,
+    pub queue: i32,
+}
+
+impl SlowStartExit {
+    /// Decode `Report.ss_exit` of `SSUpdateProg`.
+    fn from_code(code: u64) -> Self {
+        match code {
+            2 => SlowStartExit::Threshold,
+            3 => SlowStartExit::Delay,
+            _ => SlowStartExit::Loss,
+        }
+    }
+}
+
+/// Bounds on what an algorithm may set, protecting the datapath from a bad `adjust_cwnd`.
+#[derive(Debug, Clone, Copy)]
+pub struct Guardrails {
+    pub min_cwnd_pkts: u32,
+    pub max_cwnd_pkts: Option<u32>,
+    /// Rate bounds, in bytes per second. The floor is enforced as a cwnd of
+    /// `min_rate * srtt`, and the ceiling as the datapath pacing rate.
+    pub min_rate: Option<u32>,
+    pub max_rate: Option<u32>,
+}
+
+impl Default for Guardrails {
+    fn default() -> Self {
+        Guardrails {
+            min_cwnd_pkts: 1,
+            max_cwnd_pkts: None,
+            min_rate: None,
+            max_rate: None,
+        }
+    }
+}
+
+impl Guardrails {
+    fn min_cwnd(&self, mss: u32, srtt: u32) -> u32 {
+        let rate_floor = self.min_rate
+            .map_or(0, |rate| (u64::from(rate) * u64::from(srtt) / 1_000_000) as u32);
+        std::cmp::max(self.min_cwnd_pkts.saturating_mul(mss), rate_floor)
+    }
+
+    fn max_cwnd(&self, mss: u32) -> u32 {
+        self.max_cwnd_pkts.map_or(u32::MAX, |pkts| pkts.saturating_mul(mss))
+    }
 }
 
 pub trait GenericCongAvoidFlow {
@@ -54,11 +317,74 @@ pub trait GenericCongAvoidFlow {
     fn reduction(&mut self, m: &GenericCongAvoidMeasurements);
     fn reset(&mut self) {}
 
+    /// Whether cwnd is driven by the SDCCP controller (`adjust_cwnd`) rather than
+    /// by the local `increase`/`reduction` loop.
+    fn use_remote(&self) -> bool {
+        true
+    }
+
+    fn adjust_cwnd(&mut self,
+                   _network_status: &NetworkStatus,
+                   _m: &GenericCongAvoidMeasurements) {}
+
+    fn update_network_status(&mut self) -> NetworkStatus {
+        NetworkStatus::default()
+    }
+
+    /// Swap in a different algorithm, if one was requested, starting it from `state`.
+    /// Returns whether a switch happened.
+    fn maybe_switch(&mut self, _state: &FlowHandoff) -> bool {
+        false
+    }
+
+    /// Start from the state of the algorithm this flow replaces.
+    fn import_state(&mut self, state: &FlowHandoff) {
+        self.set_cwnd(state.cwnd);
+    }
+}
+
+impl<F: GenericCongAvoidFlow + ?Sized> GenericCongAvoidFlow for Box<F> {
+    fn curr_cwnd(&self) -> u32 {
+        (**self).curr_cwnd()
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        (**self).set_cwnd(cwnd)
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        (**self).increase(m)
+    }
+
+    fn reduction(&mut self, m: &GenericCongAvoidMeasurements) {
+        (**self).reduction(m)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn use_remote(&self) -> bool {
+        (**self).use_remote()
+    }
+
     fn adjust_cwnd(&mut self,
                    network_status: &NetworkStatus,
-                   m: &GenericCongAvoidMeasurements);
+                   m: &GenericCongAvoidMeasurements) {
+        (**self).adjust_cwnd(network_status, m)
+    }
+
+    fn update_network_status(&mut self) -> NetworkStatus {
+        (**self).update_network_status()
+    }
+
+    fn maybe_switch(&mut self, state: &FlowHandoff) -> bool {
+        (**self).maybe_switch(state)
+    }
 
-    fn update_network_status(&mut self) -> NetworkStatus;
+    fn import_state(&mut self, state: &FlowHandoff) {
+        (**self).import_state(state)
+    }
 }
 
 pub trait RemoteGenericCongAvoidAlg {
@@ -68,9 +394,77 @@ pub trait RemoteGenericCongAvoidAlg {
     fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
         vec![]
     }
-    fn with_args(matches: clap::ArgMatches) -> Self;
+    /// Errors describe a missing or invalid argument.
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String>
+    where
+        Self: Sized;
     fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
                 sock_id: u32) -> Self::Flow;
+
+    /// Like `new_flow`, but with the flow's addresses and ports available.
+    fn new_flow_with_info(&self, logger: Option<slog::Logger>, init_cwnd: u32,
+                          info: &DatapathInfo) -> Self::Flow {
+        self.new_flow(logger, init_cwnd, info.mss, info.sock_id)
+    }
+
+    /// Additional `Report` fields to collect in every datapath program, on top of
+    /// `measurement_fields`. Their values are passed to the flow in
+    /// `GenericCongAvoidMeasurements::extra`.
+    fn extra_fields() -> Vec<Field> {
+        vec![]
+    }
+}
+
+/// The `Report` fields backing `GenericCongAvoidMeasurements`, collected by every program.
+pub fn measurement_fields() -> Vec<Field> {
+    vec![
+        Field::new("acked", "0", "(+ Report.acked Ack.bytes_acked)"),
+        Field::new("sacked", "0", "(+ Report.sacked Ack.packets_misordered)"),
+        Field::new("loss", "0", "Ack.lost_pkts_sample"),
+        Field::new("timeout", "false", "Flow.was_timeout"),
+        Field::new("rtt", "0", "Flow.rtt_sample_us"),
+        Field::new(
+            "rtt_min",
+            &u32::MAX.to_string(),
+            "(if (> Flow.rtt_sample_us 0) (min Report.rtt_min Flow.rtt_sample_us))",
+        ),
+        Field::new(
+            "rtt_max",
+            "0",
+            "(if (> Flow.rtt_sample_us 0) (max Report.rtt_max Flow.rtt_sample_us))",
+        ),
+        // seeded with the first sample, then weighted 8/10 old, 2/10 new; ACKs without an
+        // RTT sample leave it alone (so `ewma`, which cannot be nested in `if`, is spelled out)
+        Field::new(
+            "rtt_ewma",
+            "0",
+            "(if (&& (> Flow.rtt_sample_us 0) (== Report.rtt_ewma 0)) Flow.rtt_sample_us)",
+        ).then(
+            "(if (> Flow.rtt_sample_us 0) \
+             (+ (/ (* Report.rtt_ewma 8) 10) (/ (* Flow.rtt_sample_us 2) 10)))",
+        ).persistent(),
+        Field::new("inflight", "0", "Flow.packets_in_flight"),
+        Field::new(
+            "delivered",
+            "0",
+            "(+ Report.delivered (+ Ack.bytes_acked Ack.bytes_misordered))",
+        ),
+        Field::new("rate_incoming", "0", "(max Report.rate_incoming Flow.rate_incoming)"),
+        Field::new("rate_outgoing", "0", "(max Report.rate_outgoing Flow.rate_outgoing)"),
+    ]
+}
+
+/// The `Report` fields `SSUpdateProg` uses to detect the end of slow start.
+/// `ss_exit` is 2 on reaching `ssThresh`, 3 on a delay increase, and 0 otherwise.
+fn slow_start_fields() -> Vec<Field> {
+    // consecutive samples more than eta = clamp(rtt_min / 8, 4ms, 16ms) above rtt_min
+    let delayed = "(> Flow.rtt_sample_us (+ Report.rtt_min (max 4000 (min 16000 (/ Report.rtt_min 8)))))";
+    vec![
+        Field::new("ss_delay_count", "0", &format!("(if {} (+ Report.ss_delay_count 1))", delayed))
+            .then(&format!("(!if {} 0)", delayed)),
+        Field::new("ss_exit", "0", "(!if (< Cwnd ssThresh) 2)")
+            .then("(if (&& (> delayExit 0) (> Report.ss_delay_count 7)) 3)"),
+    ]
 }
 
 pub struct Alg<A: RemoteGenericCongAvoidAlg> {
@@ -79,9 +473,52 @@ pub struct Alg<A: RemoteGenericCongAvoidAlg> {
     pub report_option: GenericCongAvoidConfigReport,
     pub ss: GenericCongAvoidConfigSS,
     pub ss_thresh: u32,
+    /// End datapath slow start on a HyStart-style delay increase.
+    pub ss_delay_exit: bool,
+    /// End CCP slow start with HyStart++.
+    pub ss_hystart: bool,
+    pub ss_controller_exit: Option<ControllerExit>,
     pub use_compensation: bool,
+    pub guardrails: Guardrails,
     pub logger: Option<slog::Logger>,
     pub alg: A,
+    /// Built once from `program_specs` and shared by every flow.
+    specs: Rc<HashMap<&'static str, ProgramSpec>>,
+}
+
+/// The datapath programs for `A`, keyed by name.
+fn program_specs<A: RemoteGenericCongAvoidAlg>() -> HashMap<&'static str, ProgramSpec> {
+    let standard = measurement_fields();
+    let extra = A::extra_fields();
+    let taken: Vec<Field> = standard.iter().cloned().chain(slow_start_fields()).collect();
+    if let Some(f) = extra.iter().find(|f| taken.iter().any(|s| s.name == f.name)) {
+        panic!("{} declares Report field {}, which is already collected", A::name(), f.name);
+    }
+
+    let base = ProgramSpec::new().fields(standard).fields(extra);
+    let mut h = HashMap::default();
+    h.insert(
+        "DatapathIntervalProg",
+        base.clone().trigger(Trigger::Urgent).trigger(Trigger::Interval),
+    );
+    h.insert(
+        "DatapathIntervalRTTProg",
+        base.clone().trigger(Trigger::Urgent).trigger(Trigger::Rtt),
+    );
+    h.insert("AckUpdateProg", base.clone().trigger(Trigger::EveryAck));
+    h.insert(
+        "SSUpdateProg",
+        base.fields(slow_start_fields())
+            .var("minCwnd", "0")
+            .var("maxCwnd", &u32::MAX.to_string())
+            .var("ssThresh", &u32::MAX.to_string())
+            .var("delayExit", "0")
+            .update("(:= Cwnd (min (+ Cwnd Ack.bytes_acked) ssThresh))")
+            .update("(:= Cwnd (min (max Cwnd minCwnd) maxCwnd))")
+            .trigger(Trigger::Urgent)
+            .trigger(Trigger::When(String::from("(> Report.ss_exit 0)"))),
+    );
+    h
 }
 
 impl<T: Ipc, A: RemoteGenericCongAvoidAlg> CongAlg<T> for Alg<A> {
@@ -92,128 +529,10 @@ impl<T: Ipc, A: RemoteGenericCongAvoidAlg> CongAlg<T> for Alg<A> {
     }
 
     fn datapath_programs(&self) -> HashMap<&'static str, String> {
-        let mut h = HashMap::default();
-        h.insert(
-            "DatapathIntervalProg",
-            "
-                (def
-                (Report
-                    (volatile acked 0)
-                    (volatile sacked 0)
-                    (volatile loss 0)
-                    (volatile timeout false)
-                    (volatile rtt 0)
-                    (volatile inflight 0)
-                )
-                (reportTime 0)
-                )
-                (when true
-                    (:= Report.inflight Flow.packets_in_flight)
-                    (:= Report.rtt Flow.rtt_sample_us)
-                    (:= Report.acked (+ Report.acked Ack.bytes_acked))
-                    (:= Report.sacked (+ Report.sacked Ack.packets_misordered))
-                    (:= Report.loss Ack.lost_pkts_sample)
-                    (:= Report.timeout Flow.was_timeout)
-                    (fallthrough)
-                )
-                (when (|| Report.timeout (> Report.loss 0))
-                    (report)
-                    (:= Micros 0)
-                )
-                (when (> Micros reportTime)
-                    (report)
-                    (:= Micros 0)
-                )
-            "
-            .to_string(),
-        );
-
-        h.insert(
-            "DatapathIntervalRTTProg",
-            "
-                (def (Report
-                    (volatile acked 0)
-                    (volatile sacked 0) 
-                    (volatile loss 0)
-                    (volatile timeout false)
-                    (volatile rtt 0)
-                    (volatile inflight 0)
-                ))
-                (when true
-                    (:= Report.inflight Flow.packets_in_flight)
-                    (:= Report.rtt Flow.rtt_sample_us)
-                    (:= Report.acked (+ Report.acked Ack.bytes_acked))
-                    (:= Report.sacked (+ Report.sacked Ack.packets_misordered))
-                    (:= Report.loss Ack.lost_pkts_sample)
-                    (:= Report.timeout Flow.was_timeout)
-                    (fallthrough)
-                )
-                (when (|| Report.timeout (> Report.loss 0))
-                    (report)
-                    (:= Micros 0)
-                )
-                (when (> Micros Flow.rtt_sample_us)
-                    (report)
-                    (:= Micros 0)
-                )
-            "
-            .to_string(),
-        );
-
-        h.insert(
-            "AckUpdateProg",
-            "
-                (def (Report
-                    (volatile acked 0)
-                    (volatile sacked 0)
-                    (volatile loss 0)
-                    (volatile timeout false)
-                    (volatile rtt 0)
-                    (volatile inflight 0)
-                ))
-                (when true
-                    (:= Report.acked (+ Report.acked Ack.bytes_acked))
-                    (:= Report.sacked (+ Report.sacked Ack.packets_misordered))
-                    (:= Report.loss Ack.lost_pkts_sample)
-                    (:= Report.timeout Flow.was_timeout)
-                    (:= Report.rtt Flow.rtt_sample_us)
-                    (:= Report.inflight Flow.packets_in_flight)
-                    (report)
-                )
-            "
-            .to_string(),
-        );
-
-        h.insert(
-            "SSUpdateProg",
-            "
-                (def (Report
-                    (volatile acked 0)
-                    (volatile sacked 0)
-                    (volatile loss 0)
-                    (volatile timeout false)
-                    (volatile rtt 0)
-                    (volatile inflight 0)
-                ))
-                (when true
-                    (:= Report.acked (+ Report.acked Ack.bytes_acked))
-                    (:= Report.sacked (+ Report.sacked Ack.packets_misordered))
-                    (:= Report.loss Ack.lost_pkts_sample)
-                    (:= Report.timeout Flow.was_timeout)
-                    (:= Report.rtt Flow.rtt_sample_us)
-                    (:= Report.inflight Flow.packets_in_flight)
-                    (:= Cwnd (+ Cwnd Ack.bytes_acked))
-                    (fallthrough)
-                )
-                (when (|| Report.timeout (> Report.loss 0))
-                    (report)
-                )
-
-            "
-            .to_string(),
-        );
-
-        h
+        self.specs
+            .iter()
+            .map(|(name, spec)| (*name, spec.build()))
+            .collect()
     }
 
     fn new_flow(&self, control: Datapath<T>, info: DatapathInfo) -> Self::Flow {
@@ -223,40 +542,61 @@ impl<T: Ipc, A: RemoteGenericCongAvoidAlg> CongAlg<T> for Alg<A> {
             info.init_cwnd
         };
 
-        let sock_id = control.get_sock_id();
         let mut s = Flow {
             control_channel: control,
+            sock_id: info.sock_id,
             logger: self.logger.clone(),
             report_option: self.report_option,
+            delivered: 0,
+            controller_timestamp: None,
+            controller_epoch: None,
+            report_interval: time::Duration::zero(),
+            guardrails: self.guardrails,
+            guardrail_hits: 0,
+            specs: self.specs.clone(),
+            program: "",
             sc: Default::default(),
+            ss: self.ss,
             ss_thresh: self.ss_thresh,
+            ss_delay_exit: self.ss_delay_exit,
+            hystart: if self.ss_hystart { Some(HyStart::default()) } else { None },
+            ss_controller_exit: self.ss_controller_exit,
+            network_status: None,
+            last_status_poll: None,
             rtt: 0,
+            srtt: 0,
             in_startup: false,
+            in_slow_start: false,
             mss: info.mss,
             use_compensation: self.use_compensation,
             deficit_timeout: self.deficit_timeout,
             init_cwnd,
             curr_cwnd_reduction: 0,
             last_cwnd_reduction: time::now().to_timespec() - time::Duration::milliseconds(500),
-            alg: self.alg.new_flow(self.logger.clone(), init_cwnd, info.mss,
-                                   sock_id),
-
-            use_remote: true,
+            alg: self.alg.new_flow_with_info(self.logger.clone(), init_cwnd, &info),
         };
 
-        match (self.ss, self.report_option) {
-            (GenericCongAvoidConfigSS::Datapath, _) => {
+        if let GenericCongAvoidConfigReport::ControllerEpoch(None) = self.report_option {
+            if !s.alg.use_remote() {
+                if let Some(log) = self.logger.as_ref() {
+                    warn!(log, "cannot learn controller epoch without a controller-driven algorithm";
+                        "sock_id" => info.sock_id,
+                    );
+                }
+            }
+        }
+
+        match self.ss {
+            GenericCongAvoidConfigSS::Datapath => {
                 s.sc = s.install_ss_update();
                 s.in_startup = true;
             }
-            (GenericCongAvoidConfigSS::Ccp, GenericCongAvoidConfigReport::Ack) => {
-                s.sc = s.install_ack_update();
+            GenericCongAvoidConfigSS::Ccp => {
+                s.sc = s.install_report_program();
+                s.in_slow_start = true;
             }
-            (GenericCongAvoidConfigSS::Ccp, GenericCongAvoidConfigReport::Rtt) => {
-                s.sc = s.install_datapath_interval_rtt();
-            }
-            (GenericCongAvoidConfigSS::Ccp, GenericCongAvoidConfigReport::Interval(i)) => {
-                s.sc = s.install_datapath_interval(i);
+            GenericCongAvoidConfigSS::Off => {
+                s.sc = s.install_report_program();
             }
         }
 
@@ -269,181 +609,360 @@ pub struct Flow<T: Ipc, A: GenericCongAvoidFlow> {
     deficit_timeout: u32,
     init_cwnd: u32,
     report_option: GenericCongAvoidConfigReport,
+    ss: GenericCongAvoidConfigSS,
     ss_thresh: u32,
+    ss_delay_exit: bool,
+    hystart: Option<HyStart>,
+    ss_controller_exit: Option<ControllerExit>,
+    network_status: Option<NetworkStatus>,
+    /// When `controller_exit_step` last fetched a status for a non-remote algorithm.
+    last_status_poll: Option<time::Timespec>,
     use_compensation: bool,
     control_channel: Datapath<T>,
+    sock_id: u32,
     logger: Option<slog::Logger>,
 
     curr_cwnd_reduction: u32,
     last_cwnd_reduction: time::Timespec,
 
     in_startup: bool,
+    /// CCP slow start is running: from the start of the flow and after each timeout,
+    /// until loss, `ss_thresh`, HyStart++ or the controller ends it. Algorithms may
+    /// lower cwnd below `ss_thresh` without loss, so this is not `cwnd < ss_thresh`.
+    in_slow_start: bool,
     mss: u32,
     rtt: u32,
+    srtt: u32,
+    delivered: u64,
+    controller_timestamp: Option<u64>,
+    controller_epoch: Option<time::Duration>,
+    report_interval: time::Duration,
+    guardrails: Guardrails,
+    /// Clamps so far; only some are logged as they happen, and the total at close.
+    guardrail_hits: u64,
+    specs: Rc<HashMap<&'static str, ProgramSpec>>,
+    program: &'static str,
     sc: Scope,
-
-    use_remote: bool,
 }
 
 impl<I: Ipc, A: GenericCongAvoidFlow> portus::Flow for Flow<I, A> {
-    fn on_report(&mut self, _sock_id: u32, m: Report) {
+    fn on_report(&mut self, sock_id: u32, m: Report) {
         let mut ms = self.get_fields(&m);
 
-        self.logger.as_ref().map(|log| {
-            debug!(log, "on report"; "sock_id" => _sock_id);
-        });
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "on report"; "sock_id" => sock_id);
+        }
 
+        let exiting_startup = self.in_startup;
         if self.in_startup {
-            // install new fold
-            match self.report_option {
-                GenericCongAvoidConfigReport::Ack => {
-                    self.sc = self.install_ack_update();
-                }
-                GenericCongAvoidConfigReport::Rtt => {
-                    self.sc = self.install_datapath_interval_rtt();
-                }
-                GenericCongAvoidConfigReport::Interval(i) => {
-                    self.sc = self.install_datapath_interval(i);
-                }
+            let reason = m.get_field("Report.ss_exit", &self.sc)
+                .map_or(SlowStartExit::Loss, SlowStartExit::from_code);
+            if reason != SlowStartExit::Loss {
+                self.ss_thresh = std::cmp::min(self.ss_thresh, ms.inflight * self.mss);
             }
+            self.log_ss_exit(reason);
+
+            // install new fold
+            self.sc = self.install_report_program();
 
             self.alg.set_cwnd(ms.inflight * self.mss);
             self.in_startup = false;
         }
 
+        let congested = exiting_startup
+            || self.in_slow_start
+            || ms.was_timeout
+            || ms.loss > 0
+            || ms.sacked > 0
+            || self.curr_cwnd_reduction > 0;
+        self.adapt_report_interval(congested);
+
         self.rtt = ms.rtt;
+        if ms.rtt > 0 {
+            self.srtt = if self.srtt == 0 {
+                ms.rtt
+            } else {
+                (7 * self.srtt + ms.rtt) / 8
+            };
+        }
+
+        let handoff = FlowHandoff {
+            cwnd: self.alg.curr_cwnd(),
+            ss_thresh: self.ss_thresh,
+            srtt: self.srtt,
+        };
+        if self.alg.maybe_switch(&handoff) {
+            // the new algorithm may not keep the imported cwnd as is, and the report can
+            // return before the cwnd is next sent
+            self.update_cwnd();
+        }
+
         if ms.was_timeout {
             self.handle_timeout();
             return;
         }
 
-        //ms.acked = self.slow_start_increase(ms.acked);
+        if let GenericCongAvoidConfigSS::Ccp = self.ss {
+            if self.in_slow_start && (ms.loss > 0 || ms.sacked > 0) {
+                // stop growing; recovery is up to the algorithm
+                self.ss_thresh = self.alg.curr_cwnd();
+                self.end_slow_start(SlowStartExit::Loss);
+            }
+
+            self.controller_exit_step(sock_id, &ms);
+            let growth_divisor = self.hystart_step(&ms);
+            ms.acked = self.slow_start_increase(ms.acked, growth_divisor);
+        }
 
-        if self.use_remote {
+        if self.alg.use_remote() {
             let network_status = self.alg.update_network_status();
             println!("{:?}", &network_status);
+            self.track_controller_epoch(&network_status);
             self.alg.adjust_cwnd(&network_status, &ms);
+            self.network_status = Some(network_status);
         } else {
             // increase the cwnd corresponding to new in-order cumulative ACKs
             self.alg.increase(&ms);
             self.maybe_reduce_cwnd(&ms);
             if self.curr_cwnd_reduction > 0 {
-                self.logger.as_ref().map(|log| {
+                if let Some(log) = self.logger.as_ref() {
                     debug!(log, "in cwnd reduction"; "acked" => ms.acked / self.mss, "deficit" => self.curr_cwnd_reduction);
-                });
+                }
                 return;
             }
         }
 
         self.update_cwnd();
 
-        self.logger.as_ref().map(|log| {
-            debug!(log, "got ack"; 
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "got ack";
                 "acked(pkts)" => ms.acked / self.mss,
                 "curr_cwnd (pkts)" => self.alg.curr_cwnd() / self.mss,
                 "inflight (pkts)" => ms.inflight,
                 "loss" => ms.loss,
                 "ssthresh" => self.ss_thresh,
+                "hystart" => ?self.hystart.as_ref().map(HyStart::phase),
                 "rtt" => ms.rtt,
             );
-        });
+        }
+    }
+
+    fn close(&mut self) {
+        if self.guardrail_hits == 0 {
+            return;
+        }
+
+        if let Some(log) = self.logger.as_ref() {
+            info!(log, "flow closed after guardrail clamps";
+                "sock_id" => self.sock_id,
+                "hits" => self.guardrail_hits,
+            );
+        }
     }
 }
 
 impl<T: Ipc, A: GenericCongAvoidFlow> Flow<T, A> {
+    fn install(&mut self, program: &'static str, fields: Option<&[(&str, u32)]>) -> Scope {
+        self.program = program;
+        let sc = self.control_channel
+            .set_program(program, fields)
+            .unwrap();
+
+        if let Some(rate) = self.guardrails.max_rate {
+            if let Err(e) = self.control_channel.update_field(&sc, &[("Rate", rate)]) {
+                if let Some(log) = self.logger.as_ref() {
+                    warn!(log, "Rate update error"; "err" => ?e);
+                }
+            }
+        }
+
+        sc
+    }
+
+    /// Install the program for the configured report mode.
+    fn install_report_program(&mut self) -> Scope {
+        match self.report_option {
+            GenericCongAvoidConfigReport::Ack => self.install_ack_update(),
+            GenericCongAvoidConfigReport::Rtt => self.install_datapath_interval_rtt(),
+            GenericCongAvoidConfigReport::Interval(i) => self.install_datapath_interval(i),
+            GenericCongAvoidConfigReport::Adaptive { min, .. } => {
+                self.report_interval = min;
+                self.install_datapath_interval(min)
+            }
+            GenericCongAvoidConfigReport::ControllerEpoch(period) => {
+                match period.or(self.controller_epoch) {
+                    Some(i) => self.install_datapath_interval(i),
+                    None => self.install_datapath_interval_rtt(),
+                }
+            }
+        }
+    }
+
+    /// In `Adaptive` mode, report frequently around congestion events and back off
+    /// exponentially otherwise.
+    fn adapt_report_interval(&mut self, congested: bool) {
+        let (min, max) = match self.report_option {
+            GenericCongAvoidConfigReport::Adaptive { min, max } => (min, max),
+            _ => return,
+        };
+
+        if self.program != "DatapathIntervalProg" {
+            return;
+        }
+
+        let interval = if congested {
+            min
+        } else {
+            std::cmp::min(self.report_interval * 2, max)
+        };
+        if interval == self.report_interval {
+            return;
+        }
+
+        self.report_interval = interval;
+        let report_time = interval.num_microseconds().unwrap() as u32;
+        if let Err(e) = self.control_channel.update_field(&self.sc, &[("reportTime", report_time)]) {
+            if let Some(log) = self.logger.as_ref() {
+                warn!(log, "reportTime update error"; "err" => ?e);
+            }
+        }
+    }
+
+    /// Learn the controller's update period from the gaps between the timestamps of
+    /// successive distinct statuses, and report at that period. Reports can span
+    /// several epochs, so the smallest gap seen is taken as the period.
+    fn track_controller_epoch(&mut self, status: &NetworkStatus) {
+        match self.report_option {
+            GenericCongAvoidConfigReport::ControllerEpoch(None) => (),
+            _ => return,
+        }
+
+        let ts = match status.timestamp_ms {
+            Some(ts) => ts,
+            None => return,
+        };
+        let last = match self.controller_timestamp.replace(ts) {
+            Some(last) if ts > last => last,
+            _ => return,
+        };
+
+        let period = time::Duration::milliseconds((ts - last) as i64);
+        if self.controller_epoch.is_some_and(|epoch| epoch <= period) {
+            return;
+        }
+
+        self.controller_epoch = Some(period);
+        if let Some(log) = self.logger.as_ref() {
+            info!(log, "controller epoch"; "period_ms" => period.num_milliseconds());
+        }
+
+        if self.in_startup {
+            // the report program is installed when slow start ends
+            return;
+        }
+
+        if self.program == "DatapathIntervalProg" {
+            let report_time = period.num_microseconds().unwrap() as u32;
+            if let Err(e) = self.control_channel.update_field(&self.sc, &[("reportTime", report_time)]) {
+                if let Some(log) = self.logger.as_ref() {
+                    warn!(log, "reportTime update error"; "err" => ?e);
+                }
+            }
+        } else {
+            self.sc = self.install_datapath_interval(period);
+        }
+    }
+
     /// Make no updates in the datapath, and send a report after an interval
     fn install_datapath_interval(&mut self, interval: time::Duration) -> Scope {
-        self.control_channel
-            .set_program(
-                "DatapathIntervalProg",
-                Some(&[("reportTime", interval.num_microseconds().unwrap() as u32)][..]),
-            )
-            .unwrap()
+        self.install(
+            "DatapathIntervalProg",
+            Some(&[("reportTime", interval.num_microseconds().unwrap() as u32)][..]),
+        )
     }
 
     /// Make no updates in the datapath, and send a report after each RTT
     fn install_datapath_interval_rtt(&mut self) -> Scope {
-        self.control_channel
-            .set_program("DatapathIntervalRTTProg", None)
-            .unwrap()
+        self.install("DatapathIntervalRTTProg", None)
     }
 
     /// Make no updates in the datapath, but send a report on every ack.
     fn install_ack_update(&mut self) -> Scope {
-        self.control_channel
-            .set_program("AckUpdateProg", None)
-            .unwrap()
+        self.install("AckUpdateProg", None)
     }
 
     /// Don't update acked, since those acks are already accounted for in slow start.
     /// Send a report once there is a drop or timeout.
     fn install_ss_update(&mut self) -> Scope {
-        self.control_channel
-            .set_program("SSUpdateProg", None)
-            .unwrap()
+        let min_cwnd = self.guardrails.min_cwnd(self.mss, self.srtt);
+        let max_cwnd = self.guardrails.max_cwnd(self.mss);
+        let ss_thresh = self.ss_thresh;
+        let delay_exit = self.ss_delay_exit as u32;
+        self.install(
+            "SSUpdateProg",
+            Some(&[
+                ("minCwnd", min_cwnd),
+                ("maxCwnd", max_cwnd),
+                ("ssThresh", ss_thresh),
+                ("delayExit", delay_exit),
+            ][..]),
+        )
+    }
+
+    fn log_ss_exit(&self, reason: SlowStartExit) {
+        if let Some(log) = self.logger.as_ref() {
+            info!(log, "slow start exit";
+                "reason" => ?reason,
+                "curr_cwnd (pkts)" => self.alg.curr_cwnd() / self.mss,
+                "ssthresh" => self.ss_thresh,
+            );
+        }
     }
 
-    fn update_cwnd(&self) {
+    fn end_slow_start(&mut self, reason: SlowStartExit) {
+        self.in_slow_start = false;
+        self.log_ss_exit(reason);
+    }
+
+    /// Clamp the algorithm's cwnd to the guardrails, and send it to the datapath.
+    fn update_cwnd(&mut self) {
+        let cwnd = self.alg.curr_cwnd();
+        let min_cwnd = self.guardrails.min_cwnd(self.mss, self.srtt);
+        let max_cwnd = self.guardrails.max_cwnd(self.mss);
+        if cwnd < min_cwnd || cwnd > max_cwnd {
+            let clamped = std::cmp::min(std::cmp::max(cwnd, min_cwnd), max_cwnd);
+            self.alg.set_cwnd(clamped);
+            self.guardrail_hits += 1;
+            match self.logger.as_ref() {
+                Some(log) if self.guardrail_hits % GUARDRAIL_WARN_EVERY == 1 => {
+                    warn!(log, "cwnd outside guardrails";
+                          "sock_id" => self.sock_id,
+                          "cwnd" => cwnd,
+                          "clamped" => clamped,
+                          "hits" => self.guardrail_hits,
+                    );
+                }
+                _ => {}
+            }
+        }
+
         if let Err(e) = self
             .control_channel
             .update_field(&self.sc, &[("Cwnd", self.alg.curr_cwnd())])
         {
-            self.logger.as_ref().map(|log| {
+            if let Some(log) = self.logger.as_ref() {
                 warn!(log, "Cwnd update error";
                       "err" => ?e,
                 );
-            });
-        }
-
-        let is_set_rate = false;
-        if is_set_rate {
-            if let Err(e) = self
-                .control_channel
-                .update_field(&self.sc, &[("Rate", 200000)])
-            {
-                self.logger.as_ref().map(|log| {
-                    warn!(log, "Cwnd update error";
-                      "err" => ?e,
-                    );
-                });
             }
         }
     }
 
     fn get_fields(&mut self, m: &Report) -> GenericCongAvoidMeasurements {
-        let sc = &self.sc;
-        let ack = m
-            .get_field(&String::from("Report.acked"), sc)
-            .expect("expected acked field in returned measurement") as u32;
-
-        let sack = m
-            .get_field(&String::from("Report.sacked"), sc)
-            .expect("expected sacked field in returned measurement") as u32;
-
-        let was_timeout =
-            m.get_field(&String::from("Report.timeout"), sc)
-                .expect("expected timeout field in returned measurement") as u32;
-
-        let inflight =
-            m.get_field(&String::from("Report.inflight"), sc)
-                .expect("expected inflight field in returned measurement") as u32;
-
-        let loss = m
-            .get_field(&String::from("Report.loss"), sc)
-            .expect("expected loss field in returned measurement") as u32;
-
-        let rtt = m
-            .get_field(&String::from("Report.rtt"), sc)
-            .expect("expected rtt field in returned measurement") as u32;
-
-        GenericCongAvoidMeasurements {
-            acked: ack,
-            was_timeout: was_timeout == 1,
-            sacked: sack,
-            loss,
-            rtt,
-            inflight,
-        }
+        let fields = self.specs[self.program].extract(m, &self.sc);
+        let ms = GenericCongAvoidMeasurements::from_fields(fields, self.delivered);
+        self.delivered = ms.delivered_total;
+        ms
     }
 
     fn handle_timeout(&mut self) {
@@ -455,16 +974,18 @@ impl<T: Ipc, A: GenericCongAvoidFlow> Flow<T, A> {
         self.alg.reset();
         self.alg.set_cwnd(self.init_cwnd);
         self.curr_cwnd_reduction = 0;
+        if let GenericCongAvoidConfigSS::Ccp = self.ss {
+            self.in_slow_start = true;
+        }
 
-        self.logger.as_ref().map(|log| {
-            warn!(log, "timeout"; 
-                "curr_cwnd (pkts)" => self.init_cwnd / self.mss, 
+        if let Some(log) = self.logger.as_ref() {
+            warn!(log, "timeout";
+                "curr_cwnd (pkts)" => self.init_cwnd / self.mss,
                 "ssthresh" => self.ss_thresh,
             );
-        });
+        }
 
         self.update_cwnd();
-        return;
     }
 
     fn maybe_reduce_cwnd(&mut self, m: &GenericCongAvoidMeasurements) {
@@ -497,27 +1018,115 @@ impl<T: Ipc, A: GenericCongAvoidFlow> Flow<T, A> {
         }
     }
 
-    fn slow_start_increase(&mut self, acked: u32) -> u32 {
+    /// During slow start, end it if the controller reports a busy bottleneck, and jump
+    /// to the flow's estimated fair share. Uses the status the algorithm last fetched,
+    /// or, if the algorithm doesn't fetch any, one fetched here at most once per RTT.
+    fn controller_exit_step(&mut self, sock_id: u32, ms: &GenericCongAvoidMeasurements) {
+        let exit = match self.ss_controller_exit {
+            Some(exit) if self.in_slow_start => exit,
+            _ => return,
+        };
+
+        if !self.alg.use_remote() {
+            self.poll_network_status(sock_id);
+        }
+
+        let cwnd = match self.network_status.as_ref() {
+            Some(status)
+                if status.link_utilization >= exit.utilization || status.queue_length > exit.queue =>
+            {
+                self.fair_share_cwnd(status, ms)
+            }
+            _ => return,
+        };
+
+        self.alg.set_cwnd(cwnd);
+        self.ss_thresh = cwnd;
+        self.hystart = None;
+        self.end_slow_start(SlowStartExit::Controller);
+    }
+
+    /// Refresh the cached `network_status` if an RTT has passed since the last attempt.
+    /// Failures are logged and leave the cached status in place.
+    fn poll_network_status(&mut self, sock_id: u32) {
+        let now = time::now().to_timespec();
+        let rtt = time::Duration::microseconds(i64::from(self.srtt));
+        if self.last_status_poll.is_some_and(|t| now - t < rtt) {
+            return;
+        }
+
+        self.last_status_poll = Some(now);
+        match NetworkStatus::try_fetch(&NetworkStatus::url(sock_id)) {
+            Ok(status) => self.network_status = Some(status),
+            Err(e) => {
+                if let Some(log) = self.logger.as_ref() {
+                    warn!(log, "controller status unavailable"; "err" => e);
+                }
+            }
+        }
+    }
+
+    /// The bandwidth-delay product the flow currently gets, or failing a delivery rate,
+    /// its cwnd less its share of the reported queue. Slow start only ever overshoots,
+    /// so this is at most the current cwnd.
+    fn fair_share_cwnd(&self, status: &NetworkStatus, ms: &GenericCongAvoidMeasurements) -> u32 {
+        let cwnd = self.alg.curr_cwnd();
+        let estimate = if ms.rate_incoming > 0 && ms.rtt_min > 0 {
+            (ms.rate_incoming * u64::from(ms.rtt_min) / 1_000_000) as u32
+        } else {
+            let queue_share = status.queue_length.max(0) as u32 / status.flow_count.max(1);
+            cwnd.saturating_sub(queue_share)
+        };
+
+        std::cmp::min(std::cmp::max(estimate, self.init_cwnd), cwnd)
+    }
+
+    /// Feed a report to HyStart++ during slow start, ending slow start if it says so.
+    /// Returns the divisor of slow start growth.
+    fn hystart_step(&mut self, ms: &GenericCongAvoidMeasurements) -> u32 {
+        if !self.in_slow_start {
+            return 1;
+        }
+
+        let phase = match self.hystart.as_mut() {
+            Some(hystart) => hystart.on_report(self.srtt, ms.rtt_min, ms.acked / self.mss),
+            None => return 1,
+        };
+        match phase {
+            HyStartPhase::SlowStart => 1,
+            HyStartPhase::Conservative => CSS_GROWTH_DIVISOR,
+            HyStartPhase::Done => {
+                // slow starts after a timeout run to ss_thresh
+                self.hystart = None;
+                self.ss_thresh = self.alg.curr_cwnd();
+                self.end_slow_start(SlowStartExit::Delay);
+                1
+            }
+        }
+    }
+
+    fn slow_start_increase(&mut self, acked: u32, growth_divisor: u32) -> u32 {
         let mut new_bytes_acked = acked;
-        if self.alg.curr_cwnd() < self.ss_thresh {
+        if self.in_slow_start {
             // increase cwnd by 1 per packet, until ssthresh
-            if self.alg.curr_cwnd() + new_bytes_acked > self.ss_thresh {
-                new_bytes_acked -= self.ss_thresh - self.alg.curr_cwnd();
-                self.alg.set_cwnd(self.ss_thresh);
+            let curr_cwnd = self.alg.curr_cwnd();
+            if curr_cwnd.saturating_add(new_bytes_acked) > self.ss_thresh {
+                new_bytes_acked -= self.ss_thresh.saturating_sub(curr_cwnd);
+                self.alg.set_cwnd(std::cmp::max(curr_cwnd, self.ss_thresh));
+                self.end_slow_start(SlowStartExit::Threshold);
             } else {
-                let curr_cwnd = self.alg.curr_cwnd();
                 if self.use_compensation {
                     // use a compensating increase function: deliberately overshoot
                     // the "correct" update to keep account for lost throughput due to
                     // infrequent updates. Usually this doesn't matter, but it can when
                     // the window is increasing exponentially (slow start).
-                    let delta = f64::from(new_bytes_acked) / (2.0_f64).ln();
+                    let delta = f64::from(new_bytes_acked / growth_divisor) / (2.0_f64).ln();
                     self.alg.set_cwnd(curr_cwnd + delta as u32);
                 // let ccp_rtt = (rtt_us + 10_000) as f64;
                 // let delta = ccp_rtt * ccp_rtt / (rtt_us as f64 * rtt_us as f64);
                 // self.cwnd += (new_bytes_acked as f64 * delta) as u32;
                 } else {
-                    self.alg.set_cwnd(curr_cwnd + new_bytes_acked);
+                    self.alg.set_cwnd(curr_cwnd + new_bytes_acked / growth_divisor);
                 }
 
                 new_bytes_acked = 0
@@ -527,3 +1136,96 @@ impl<T: Ipc, A: GenericCongAvoidFlow> Flow<T, A> {
         new_bytes_acked
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{program_specs, Field, GenericCongAvoidMeasurements, RemoteGenericCongAvoidAlg};
+    use policy::Policy;
+    use portus::lang;
+    use reno::Reno;
+
+    /// Reno, collecting the bytes ECN-marked since the last report.
+    struct Ecn;
+
+    impl RemoteGenericCongAvoidAlg for Ecn {
+        type Flow = Reno;
+
+        fn name() -> &'static str {
+            "ecn"
+        }
+
+        fn with_args(_matches: clap::ArgMatches) -> Result<Self, String> {
+            Ok(Ecn)
+        }
+
+        fn new_flow(&self, _logger: Option<slog::Logger>, _init_cwnd: u32, _mss: u32,
+                    _sock_id: u32) -> Self::Flow {
+            Reno::default()
+        }
+
+        fn extra_fields() -> Vec<Field> {
+            vec![Field::new("ecn_bytes", "0", "(+ Report.ecn_bytes Ack.ecn_bytes)")]
+        }
+    }
+
+    /// Declares a field `SSUpdateProg` already collects.
+    struct Clashing;
+
+    impl RemoteGenericCongAvoidAlg for Clashing {
+        type Flow = Reno;
+
+        fn name() -> &'static str {
+            "clashing"
+        }
+
+        fn with_args(_matches: clap::ArgMatches) -> Result<Self, String> {
+            Ok(Clashing)
+        }
+
+        fn new_flow(&self, _logger: Option<slog::Logger>, _init_cwnd: u32, _mss: u32,
+                    _sock_id: u32) -> Self::Flow {
+            Reno::default()
+        }
+
+        fn extra_fields() -> Vec<Field> {
+            vec![Field::new("ss_exit", "0", "0")]
+        }
+    }
+
+    #[test]
+    fn datapath_programs_compile() {
+        // the policy programs collect every algorithm's extra fields
+        let specs = program_specs::<Reno>().into_iter().chain(program_specs::<Policy>());
+        for (name, spec) in specs {
+            let src = spec.build();
+            if let Err(e) = lang::compile(src.as_bytes(), &[]) {
+                panic!("{} does not compile: {:?}\n{}", name, e, src);
+            }
+        }
+    }
+
+    #[test]
+    fn extra_fields_reach_measurements() {
+        for (name, spec) in program_specs::<Ecn>() {
+            let src = spec.build();
+            let sc = match lang::compile(src.as_bytes(), &[]) {
+                Ok((_, sc)) => sc,
+                Err(e) => panic!("{} does not compile: {:?}\n{}", name, e, src),
+            };
+
+            let fields = spec.read(|field| match field {
+                _ if sc.get(field).is_none() => None,
+                "Report.ecn_bytes" => Some(2896),
+                _ => Some(0),
+            });
+            let ms = GenericCongAvoidMeasurements::from_fields(fields, 0);
+            assert_eq!(ms.extra.get("ecn_bytes"), Some(2896), "{}", name);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ss_exit")]
+    fn slow_start_fields_cannot_be_redeclared() {
+        program_specs::<Clashing>();
+    }
+}
@@ -0,0 +1,187 @@
+use clap::Arg;
+use time::{self, Timespec};
+
+use ::{parse_arg, RemoteGenericCongAvoidAlg, NetworkStatus, StatusChange};
+use pid_controller::PidController;
+use GenericCongAvoidFlow;
+use GenericCongAvoidMeasurements;
+
+#[derive(Clone, Copy, Debug)]
+pub enum PidSignal {
+    Utilization,
+    Queue,
+}
+
+/// Drive cwnd with a PID controller on the SDCCP controller's reports.
+///
+/// The error is either `util_target - link_utilization`, or the distance of the reported
+/// queue from `queue_target`, in packets. On each controller update, cwnd is scaled by
+/// `1 + output`; reports between updates leave it unchanged.
+#[derive(Clone)]
+pub struct Pid {
+    mss: u32,
+    init_cwnd: f64,
+    cwnd: f64,
+
+    signal: PidSignal,
+    util_target: f32,
+    queue_target: f64,
+    controller: PidController,
+
+    last_update: Option<Timespec>,
+    status_change: StatusChange,
+    server_url: String,
+}
+
+impl RemoteGenericCongAvoidAlg for Pid {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "pid"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("pid_signal")
+                .long("pid_signal")
+                .help("Controller report the error is computed from")
+                .possible_values(&["utilization", "queue"])
+                .default_value("utilization"),
+            Arg::with_name("pid_util_target")
+                .long("pid_util_target")
+                .help("Target link utilization")
+                .default_value("0.95"),
+            Arg::with_name("pid_queue_target")
+                .long("pid_queue_target")
+                .help("Target standing queue, in bytes")
+                .default_value("15000"),
+            Arg::with_name("pid_kp")
+                .long("pid_kp")
+                .help("Proportional gain")
+                .default_value("0.5"),
+            Arg::with_name("pid_ki")
+                .long("pid_ki")
+                .help("Integral gain (per second)")
+                .default_value("0.1"),
+            Arg::with_name("pid_kd")
+                .long("pid_kd")
+                .help("Derivative gain (seconds)")
+                .default_value("0"),
+            Arg::with_name("pid_output_min")
+                .long("pid_output_min")
+                .help("Lower bound of the relative cwnd change per update")
+                .default_value("-0.5"),
+            Arg::with_name("pid_output_max")
+                .long("pid_output_max")
+                .help("Upper bound of the relative cwnd change per update")
+                .default_value("0.5"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Pid {
+            mss: 0,
+            init_cwnd: 0.0,
+            cwnd: 0.0,
+
+            signal: match matches.value_of("pid_signal").unwrap() {
+                "queue" => PidSignal::Queue,
+                _ => PidSignal::Utilization,
+            },
+            util_target: parse_arg(&matches, "pid_util_target")?,
+            queue_target: parse_arg(&matches, "pid_queue_target")?,
+            controller: PidController::new(
+                parse_arg(&matches, "pid_kp")?,
+                parse_arg(&matches, "pid_ki")?,
+                parse_arg(&matches, "pid_kd")?,
+                parse_arg(&matches, "pid_output_min")?,
+                parse_arg(&matches, "pid_output_max")?,
+            ),
+
+            last_update: None,
+            status_change: StatusChange::default(),
+            server_url: String::new(),
+        })
+    }
+
+    fn new_flow(&self, _logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                sock_id: u32) -> Self::Flow {
+        Pid {
+            mss,
+            init_cwnd: f64::from(init_cwnd),
+            cwnd: f64::from(init_cwnd),
+            server_url: NetworkStatus::url(sock_id),
+            ..self.clone()
+        }
+    }
+}
+
+impl Pid {
+    fn error(&self, network_status: &NetworkStatus) -> f64 {
+        match self.signal {
+            PidSignal::Utilization => {
+                f64::from(self.util_target - network_status.link_utilization.max(0.0))
+            }
+            PidSignal::Queue => {
+                let queue = f64::from(network_status.queue_length.max(0));
+                (self.queue_target - queue) / f64::from(self.mss)
+            }
+        }
+    }
+}
+
+impl GenericCongAvoidFlow for Pid {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        // increase cwnd by 1 / cwnd per packet
+        self.cwnd += f64::from(self.mss) * (f64::from(m.acked) / self.cwnd);
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        self.cwnd /= 2.0;
+        if self.cwnd <= self.init_cwnd {
+            self.cwnd = self.init_cwnd;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.controller.reset();
+        self.last_update = None;
+        self.status_change = StatusChange::default();
+    }
+
+    fn adjust_cwnd(&mut self,
+                   network_status: &NetworkStatus,
+                   _m: &GenericCongAvoidMeasurements)
+    {
+        if !self.status_change.is_new(network_status) {
+            return;
+        }
+
+        let now = time::now().to_timespec();
+        let dt = self.last_update
+            .and_then(|t| (now - t).num_microseconds())
+            .map_or(0.0, |us| us as f64 / 1e6);
+        self.last_update = Some(now);
+
+        let error = self.error(network_status);
+        let output = self.controller.update(error, dt);
+        self.cwnd *= 1.0 + output;
+
+        let min_cwnd = 2.0 * f64::from(self.mss);
+        if self.cwnd < min_cwnd {
+            self.cwnd = min_cwnd;
+        }
+    }
+
+    fn update_network_status(&mut self) -> NetworkStatus {
+        NetworkStatus::fetch(&self.server_url)
+    }
+}
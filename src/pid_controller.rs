@@ -0,0 +1,58 @@
+/// A PID feedback controller with output clamping and anti-windup.
+///
+/// Anti-windup is done by conditional integration: while the output is saturated, error
+/// that would push it further into saturation is not accumulated. Setting `kd` to zero
+/// gives a PI controller.
+#[derive(Clone, Debug)]
+pub struct PidController {
+    pub kp: f64,
+    pub ki: f64,
+    pub kd: f64,
+    pub output_min: f64,
+    pub output_max: f64,
+
+    integral: f64,
+    prev_error: Option<f64>,
+}
+
+impl PidController {
+    pub fn new(kp: f64, ki: f64, kd: f64, output_min: f64, output_max: f64) -> Self {
+        PidController {
+            kp,
+            ki,
+            kd,
+            output_min,
+            output_max,
+
+            integral: 0.0,
+            prev_error: None,
+        }
+    }
+
+    /// Feed the current error (target - measured), `dt` seconds after the previous update,
+    /// and get the clamped controller output.
+    pub fn update(&mut self, error: f64, dt: f64) -> f64 {
+        let derivative = match self.prev_error {
+            Some(prev) if dt > 0.0 => (error - prev) / dt,
+            _ => 0.0,
+        };
+        self.prev_error = Some(error);
+
+        let integral = self.integral + error * dt;
+        let unclamped = self.kp * error + self.ki * integral + self.kd * derivative;
+        let output = unclamped.max(self.output_min).min(self.output_max);
+
+        let saturated_high = unclamped > self.output_max && error > 0.0;
+        let saturated_low = unclamped < self.output_min && error < 0.0;
+        if !(saturated_high || saturated_low) {
+            self.integral = integral;
+        }
+
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.prev_error = None;
+    }
+}
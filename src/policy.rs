@@ -0,0 +1,671 @@
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::net::Ipv4Addr;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use clap::Arg;
+use portus::DatapathInfo;
+use serde_json;
+
+use registry::{algorithms, FlowFactory};
+use {Field, FlowHandoff, GenericCongAvoidFlow, GenericCongAvoidMeasurements, NetworkStatus,
+     RemoteGenericCongAvoidAlg};
+
+/// Minimum time between checks of the policy file for changes.
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One entry of the policy file. Omitted matchers match anything.
+#[derive(Deserialize, Debug)]
+struct RuleConfig {
+    src_ip: Option<String>,
+    dst_ip: Option<String>,
+    src_port: Option<String>,
+    dst_port: Option<String>,
+    alg: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AlgConfig {
+    alg: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PolicyConfig {
+    default: AlgConfig,
+    #[serde(default)]
+    rules: Vec<RuleConfig>,
+}
+
+/// An IPv4 prefix, e.g. `10.1.0.0/16`. A bare address is a /32.
+#[derive(Debug, Clone, Copy)]
+struct Prefix {
+    addr: u32,
+    mask: u32,
+}
+
+impl Prefix {
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = s.splitn(2, '/');
+        let addr: Ipv4Addr = parts.next().unwrap().parse()
+            .map_err(|e| format!("bad address {:?}: {}", s, e))?;
+        let len: u32 = match parts.next() {
+            Some(len) => len.parse().map_err(|e| format!("bad prefix length {:?}: {}", s, e))?,
+            None => 32,
+        };
+        if len > 32 {
+            return Err(format!("bad prefix length {:?}", s));
+        }
+
+        let mask = if len == 0 { 0 } else { !0u32 << (32 - len) };
+        Ok(Prefix {
+            addr: u32::from(addr) & mask,
+            mask,
+        })
+    }
+
+    fn matches(&self, ip: u32) -> bool {
+        ip & self.mask == self.addr
+    }
+}
+
+/// A port, e.g. `873`, or an inclusive range, e.g. `5000-5100`.
+#[derive(Debug, Clone, Copy)]
+struct PortRange {
+    lo: u32,
+    hi: u32,
+}
+
+impl PortRange {
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = s.splitn(2, '-');
+        let lo: u32 = parts.next().unwrap().trim().parse()
+            .map_err(|e| format!("bad port {:?}: {}", s, e))?;
+        let hi: u32 = match parts.next() {
+            Some(hi) => hi.trim().parse().map_err(|e| format!("bad port {:?}: {}", s, e))?,
+            None => lo,
+        };
+        if lo > hi || hi > u32::from(u16::MAX) {
+            return Err(format!("bad port range {:?}", s));
+        }
+
+        Ok(PortRange { lo, hi })
+    }
+
+    fn matches(&self, port: u32) -> bool {
+        self.lo <= port && port <= self.hi
+    }
+}
+
+fn parse_opt<T>(s: &Option<String>, parse: fn(&str) -> Result<T, String>) -> Result<Option<T>, String> {
+    s.as_ref().map(|s| parse(s)).transpose()
+}
+
+struct Rule {
+    name: String,
+    src_ip: Option<Prefix>,
+    dst_ip: Option<Prefix>,
+    src_port: Option<PortRange>,
+    dst_port: Option<PortRange>,
+    factory: Box<dyn FlowFactory>,
+}
+
+impl Rule {
+    fn matches(&self, info: &DatapathInfo) -> bool {
+        self.src_ip.is_none_or(|p| p.matches(info.src_ip))
+            && self.dst_ip.is_none_or(|p| p.matches(info.dst_ip))
+            && self.src_port.is_none_or(|p| p.matches(info.src_port))
+            && self.dst_port.is_none_or(|p| p.matches(info.dst_port))
+    }
+}
+
+fn make_factory(alg: &str, args: &[String]) -> Result<Box<dyn FlowFactory>, String> {
+    if alg == Policy::name() {
+        return Err(String::from("policies cannot be nested"));
+    }
+
+    let algs = algorithms();
+    let registration = algs.iter()
+        .find(|r| r.name == alg)
+        .ok_or_else(|| format!("unknown algorithm {:?}", alg))?;
+    registration.factory_from_args(args)
+        .map_err(|e| format!("bad arguments for {}: {}", alg, e))
+}
+
+/// The parsed policy file, reloaded when the file changes.
+struct PolicyTable {
+    path: String,
+    modified: Option<SystemTime>,
+    last_check: SystemTime,
+    rules: Vec<Rule>,
+    default_name: String,
+    default: Box<dyn FlowFactory>,
+    /// Incremented on every reload.
+    generation: u64,
+}
+
+impl PolicyTable {
+    fn load(path: &str) -> Result<Self, String> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let file = File::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        let config: PolicyConfig = serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| format!("cannot parse {}: {}", path, e))?;
+
+        let rules = config.rules.iter()
+            .map(|r| Ok(Rule {
+                name: r.alg.clone(),
+                src_ip: parse_opt(&r.src_ip, Prefix::parse)?,
+                dst_ip: parse_opt(&r.dst_ip, Prefix::parse)?,
+                src_port: parse_opt(&r.src_port, PortRange::parse)?,
+                dst_port: parse_opt(&r.dst_port, PortRange::parse)?,
+                factory: make_factory(&r.alg, &r.args)?,
+            }))
+            .collect::<Result<_, String>>()?;
+
+        Ok(PolicyTable {
+            path: path.to_string(),
+            modified,
+            last_check: SystemTime::now(),
+            rules,
+            default_name: config.default.alg.clone(),
+            default: make_factory(&config.default.alg, &config.default.args)?,
+            generation: 0,
+        })
+    }
+
+    /// Reload the file if it changed since it was loaded, checking at most once per
+    /// `RELOAD_CHECK_INTERVAL`. Returns whether the table was replaced. A file that fails
+    /// to load leaves the current table in place.
+    fn refresh(&mut self, logger: Option<&slog::Logger>) -> bool {
+        let now = SystemTime::now();
+        match now.duration_since(self.last_check) {
+            Ok(elapsed) if elapsed < RELOAD_CHECK_INTERVAL => return false,
+            _ => self.last_check = now,
+        }
+
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified == self.modified {
+            return false;
+        }
+
+        match PolicyTable::load(&self.path) {
+            Ok(mut table) => {
+                table.generation = self.generation + 1;
+                *self = table;
+                true
+            }
+            Err(e) => {
+                if let Some(log) = logger {
+                    warn!(log, "policy reload failed"; "err" => e);
+                }
+                self.modified = modified;
+                false
+            }
+        }
+    }
+
+    fn lookup(&self, info: &DatapathInfo) -> (&str, &dyn FlowFactory) {
+        match self.rules.iter().find(|r| r.matches(info)) {
+            Some(rule) => (&rule.name, &*rule.factory),
+            None => (&self.default_name, &*self.default),
+        }
+    }
+
+    /// `alg` as configured in the file: from a rule for it that matches `info`, else the
+    /// default entry, else any rule for it.
+    fn configured(&self, alg: &str, info: &DatapathInfo) -> Option<&dyn FlowFactory> {
+        let mut rules = self.rules.iter().filter(|r| r.name == alg);
+        if let Some(rule) = rules.clone().find(|r| r.matches(info)) {
+            return Some(&*rule.factory);
+        }
+
+        if self.default_name == alg {
+            return Some(&*self.default);
+        }
+
+        rules.next().map(|r| &*r.factory)
+    }
+}
+
+/// Choose each flow's algorithm from a table of 4-tuple matchers, loaded from JSON:
+///
+/// ```json
+/// {
+///   "default": { "alg": "reno" },
+///   "rules": [
+///     { "dst_port": "873", "alg": "ledbat", "args": ["--ledbat_target_ms", "25"] },
+///     { "dst_ip": "10.1.0.0/16", "src_port": "5000-5100", "alg": "hpcc" }
+///   ]
+/// }
+/// ```
+///
+/// Rules are tried in order and the first match wins; flows matching no rule use
+/// `default`. `args` are the chosen algorithm's own command line arguments. Addresses
+/// are compared as reported by the datapath, most significant byte first.
+///
+/// Flows switch algorithms mid-flow, keeping their cwnd, when the file is edited so that
+/// a different algorithm matches them, or when the SDCCP controller names an algorithm
+/// in its `alg` field. The latter is only seen by controller-driven algorithms, unless
+/// `poll_controller` is set. A commanded algorithm is built with the arguments the file
+/// gives it, if any, and runs until the controller names another or the file changes.
+pub struct Policy {
+    table: Rc<RefCell<PolicyTable>>,
+    poll_controller: bool,
+}
+
+impl Policy {
+    pub fn load(path: &str) -> Result<Self, String> {
+        Ok(Policy {
+            table: Rc::new(RefCell::new(PolicyTable::load(path)?)),
+            poll_controller: false,
+        })
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Policy {
+    type Flow = Switchable;
+
+    fn name() -> &'static str {
+        "policy"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("policy_file")
+                .long("policy_file")
+                .takes_value(true)
+                .help("JSON file mapping flow 4-tuples to algorithms"),
+            Arg::with_name("policy_poll_controller")
+                .long("policy_poll_controller")
+                .help("Poll the SDCCP controller once per RTT for algorithm switch commands"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        let path = matches.value_of("policy_file")
+            .ok_or("--policy_file is required")?;
+        let mut policy = Policy::load(path)
+            .map_err(|e| format!("invalid policy: {}", e))?;
+        policy.poll_controller = matches.is_present("policy_poll_controller");
+        Ok(policy)
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                sock_id: u32) -> Self::Flow {
+        let info = DatapathInfo {
+            sock_id,
+            init_cwnd,
+            mss,
+            src_ip: 0,
+            src_port: 0,
+            dst_ip: 0,
+            dst_port: 0,
+        };
+        self.new_flow_with_info(logger, init_cwnd, &info)
+    }
+
+    /// Flows can switch to any algorithm, so collect the extra fields of all of them.
+    fn extra_fields() -> Vec<Field> {
+        let mut fields: Vec<Field> = vec![];
+        for alg in algorithms().iter().filter(|a| a.name != Policy::name()) {
+            for f in alg.extra_fields() {
+                match fields.iter().find(|g| g.name == f.name) {
+                    Some(g) if *g != f => {
+                        panic!("{} redefines Report field {}", alg.name, f.name)
+                    }
+                    Some(_) => {}
+                    None => fields.push(f),
+                }
+            }
+        }
+
+        fields
+    }
+
+    fn new_flow_with_info(&self, logger: Option<slog::Logger>, init_cwnd: u32,
+                          info: &DatapathInfo) -> Self::Flow {
+        let (name, inner) = {
+            let table = self.table.borrow();
+            let (name, factory) = table.lookup(info);
+            (name.to_string(), factory.new_boxed_flow(logger.clone(), init_cwnd, info))
+        };
+
+        if let Some(log) = logger.as_ref() {
+            debug!(log, "policy match";
+                "sock_id" => info.sock_id,
+                "alg" => &name,
+            );
+        }
+
+        Switchable {
+            logger,
+            info: info.clone(),
+            init_cwnd,
+            table: self.table.clone(),
+            poll_controller: self.poll_controller,
+            server_url: NetworkStatus::url(info.sock_id),
+            last_poll: SystemTime::now(),
+            commanded: None,
+            commanded_generation: None,
+            name,
+            inner,
+        }
+    }
+}
+
+/// A flow whose algorithm can be replaced mid-flow.
+pub struct Switchable {
+    logger: Option<slog::Logger>,
+    info: DatapathInfo,
+    /// The flow's initial cwnd, which switched-to algorithms start from before importing
+    /// the current one.
+    init_cwnd: u32,
+    table: Rc<RefCell<PolicyTable>>,
+    poll_controller: bool,
+    server_url: String,
+    last_poll: SystemTime,
+    /// Algorithm requested by the controller and not yet applied.
+    commanded: Option<String>,
+    /// The policy table generation in effect when a commanded switch was applied. Until
+    /// the table is reloaded, it does not override the commanded algorithm.
+    commanded_generation: Option<u64>,
+
+    name: String,
+    inner: Box<dyn GenericCongAvoidFlow>,
+}
+
+impl Switchable {
+    fn poll_controller(&mut self, srtt: u32) {
+        let now = SystemTime::now();
+        match now.duration_since(self.last_poll) {
+            Ok(elapsed) if elapsed < Duration::from_micros(u64::from(srtt)) => return,
+            _ => self.last_poll = now,
+        }
+
+        let status = NetworkStatus::fetch(&self.server_url);
+        if status.alg.is_some() {
+            self.commanded = status.alg;
+        }
+    }
+
+    /// The algorithm this flow should be running, and a new instance of it if that is
+    /// not the current one.
+    fn replacement(&mut self) -> Option<(String, Box<dyn GenericCongAvoidFlow>)> {
+        let mut table = self.table.borrow_mut();
+        table.refresh(self.logger.as_ref());
+
+        if let Some(alg) = self.commanded.take() {
+            if alg == self.name {
+                return None;
+            }
+
+            let flow = match table.configured(&alg, &self.info) {
+                Some(factory) => Ok(factory.new_boxed_flow(self.logger.clone(), self.init_cwnd, &self.info)),
+                None => make_factory(&alg, &[])
+                    .map(|factory| factory.new_boxed_flow(self.logger.clone(), self.init_cwnd, &self.info)),
+            };
+            return match flow {
+                Ok(flow) => {
+                    self.commanded_generation = Some(table.generation);
+                    Some((alg, flow))
+                }
+                Err(e) => {
+                    if let Some(log) = self.logger.as_ref() {
+                        warn!(log, "cannot switch algorithm"; "alg" => &alg, "err" => e);
+                    }
+                    None
+                }
+            };
+        }
+
+        if self.commanded_generation == Some(table.generation) {
+            return None;
+        }
+
+        self.commanded_generation = None;
+        let (name, factory) = table.lookup(&self.info);
+        if name == self.name {
+            return None;
+        }
+
+        Some((name.to_string(), factory.new_boxed_flow(self.logger.clone(), self.init_cwnd, &self.info)))
+    }
+}
+
+impl GenericCongAvoidFlow for Switchable {
+    fn curr_cwnd(&self) -> u32 {
+        self.inner.curr_cwnd()
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.inner.set_cwnd(cwnd)
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        self.inner.increase(m)
+    }
+
+    fn reduction(&mut self, m: &GenericCongAvoidMeasurements) {
+        self.inner.reduction(m)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset()
+    }
+
+    fn use_remote(&self) -> bool {
+        self.inner.use_remote()
+    }
+
+    fn adjust_cwnd(&mut self,
+                   network_status: &NetworkStatus,
+                   m: &GenericCongAvoidMeasurements) {
+        self.inner.adjust_cwnd(network_status, m)
+    }
+
+    fn update_network_status(&mut self) -> NetworkStatus {
+        let status = self.inner.update_network_status();
+        if status.alg.is_some() {
+            self.commanded = status.alg.clone();
+        }
+        status
+    }
+
+    fn maybe_switch(&mut self, state: &FlowHandoff) -> bool {
+        if self.poll_controller && !self.inner.use_remote() {
+            self.poll_controller(state.srtt);
+        }
+
+        match self.replacement() {
+            Some((name, mut inner)) => {
+                inner.import_state(state);
+                if let Some(log) = self.logger.as_ref() {
+                    info!(log, "switching algorithm";
+                        "sock_id" => self.info.sock_id,
+                        "from" => &self.name,
+                        "to" => &name,
+                    );
+                }
+                self.name = name;
+                self.inner = inner;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn import_state(&mut self, state: &FlowHandoff) {
+        self.inner.import_state(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::UNIX_EPOCH;
+    use std::{env, fs, process, ptr};
+
+    use portus::DatapathInfo;
+
+    use super::{make_factory, Policy, PortRange, Prefix};
+    use registry::FlowFactory;
+    use {FlowHandoff, GenericCongAvoidFlow, RemoteGenericCongAvoidAlg};
+
+    fn ip(s: &str) -> u32 {
+        u32::from(s.parse::<::std::net::Ipv4Addr>().unwrap())
+    }
+
+    #[test]
+    fn prefix_matches_within_length() {
+        let p = Prefix::parse("10.1.0.0/16").unwrap();
+        assert!(p.matches(ip("10.1.0.0")));
+        assert!(p.matches(ip("10.1.255.7")));
+        assert!(!p.matches(ip("10.2.0.1")));
+    }
+
+    #[test]
+    fn prefix_masks_host_bits() {
+        let p = Prefix::parse("10.1.2.3/16").unwrap();
+        assert!(p.matches(ip("10.1.9.9")));
+    }
+
+    #[test]
+    fn bare_address_is_host_prefix() {
+        let p = Prefix::parse("192.168.0.1").unwrap();
+        assert!(p.matches(ip("192.168.0.1")));
+        assert!(!p.matches(ip("192.168.0.2")));
+    }
+
+    #[test]
+    fn zero_length_prefix_matches_everything() {
+        let p = Prefix::parse("0.0.0.0/0").unwrap();
+        assert!(p.matches(ip("1.2.3.4")));
+        assert!(p.matches(ip("255.255.255.255")));
+    }
+
+    #[test]
+    fn bad_prefixes_are_rejected() {
+        assert!(Prefix::parse("10.1.0.0/33").is_err());
+        assert!(Prefix::parse("10.1.0/16").is_err());
+        assert!(Prefix::parse("10.1.0.0/x").is_err());
+    }
+
+    #[test]
+    fn port_range_is_inclusive() {
+        let r = PortRange::parse("5000-5100").unwrap();
+        assert!(r.matches(5000));
+        assert!(r.matches(5100));
+        assert!(!r.matches(4999));
+        assert!(!r.matches(5101));
+    }
+
+    #[test]
+    fn single_port() {
+        let r = PortRange::parse(" 873 ").unwrap();
+        assert!(r.matches(873));
+        assert!(!r.matches(874));
+    }
+
+    #[test]
+    fn bad_port_ranges_are_rejected() {
+        assert!(PortRange::parse("5100-5000").is_err());
+        assert!(PortRange::parse("65536").is_err());
+        assert!(PortRange::parse("1-70000").is_err());
+        assert!(PortRange::parse("http").is_err());
+    }
+
+    #[test]
+    fn bad_algorithm_arguments_are_errors() {
+        assert!(make_factory("copa", &[String::from("--copa_delta"), String::from("x")]).is_err());
+        assert!(make_factory("learned", &[]).is_err());
+        assert!(make_factory("nonexistent", &[]).is_err());
+        assert!(make_factory("policy", &[]).is_err());
+        assert!(make_factory("copa", &[String::from("--copa_delta"), String::from("0.1")]).is_ok());
+    }
+
+    /// A policy loaded from `json`, and the file it was loaded from.
+    fn load(test: &str, json: &str) -> (Policy, PathBuf) {
+        let path = env::temp_dir().join(format!("ccp-policy-{}-{}.json", process::id(), test));
+        fs::write(&path, json).unwrap();
+        (Policy::load(path.to_str().unwrap()).unwrap(), path)
+    }
+
+    fn handoff() -> FlowHandoff {
+        FlowHandoff { cwnd: 40 * 1448, ss_thresh: 30 * 1448, srtt: 10_000 }
+    }
+
+    #[test]
+    fn commanded_switch_to_unbuildable_algorithm_is_ignored() {
+        let (policy, path) = load("unbuildable", r#"{"default": {"alg": "bic"}}"#);
+        fs::remove_file(&path).unwrap();
+
+        let mut flow = policy.new_flow(None, 10 * 1448, 1448, 1);
+        flow.commanded = Some(String::from("learned"));
+        assert!(!flow.maybe_switch(&handoff()));
+        assert!(flow.commanded.is_none());
+        assert_eq!(flow.name, "bic");
+
+        flow.commanded = Some(String::from("scalable"));
+        assert!(flow.maybe_switch(&handoff()));
+        assert_eq!(flow.name, "scalable");
+        assert_eq!(flow.curr_cwnd(), handoff().cwnd);
+    }
+
+    #[test]
+    fn commanded_algorithm_lasts_until_policy_reload() {
+        let (policy, path) = load("reload", r#"{"default": {"alg": "bic"}}"#);
+        let mut flow = policy.new_flow(None, 10 * 1448, 1448, 1);
+
+        flow.commanded = Some(String::from("scalable"));
+        assert!(flow.maybe_switch(&handoff()));
+        assert!(flow.commanded.is_none());
+        assert!(!flow.maybe_switch(&handoff()));
+        assert_eq!(flow.name, "scalable");
+
+        fs::write(&path, r#"{"default": {"alg": "highspeed"}}"#).unwrap();
+        {
+            let mut table = policy.table.borrow_mut();
+            table.modified = None;
+            table.last_check = UNIX_EPOCH;
+        }
+        assert!(flow.maybe_switch(&handoff()));
+        assert_eq!(flow.name, "highspeed");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn commanded_algorithm_uses_configured_arguments() {
+        let (policy, path) = load("configured", r#"{
+            "default": {"alg": "copa", "args": ["--copa_delta", "0.1"]},
+            "rules": [
+                {"dst_port": "1-100", "alg": "bic", "args": ["--bic_beta", "0.7"]},
+                {"dst_port": "200", "alg": "bic", "args": ["--bic_beta", "0.9"]}
+            ]
+        }"#);
+        fs::remove_file(&path).unwrap();
+
+        let table = policy.table.borrow();
+        let info = DatapathInfo {
+            sock_id: 1,
+            init_cwnd: 14480,
+            mss: 1448,
+            src_ip: 0,
+            src_port: 0,
+            dst_ip: 0,
+            dst_port: 200,
+        };
+        let same = |a: Option<&dyn FlowFactory>, b: &dyn FlowFactory| {
+            ptr::eq(a.unwrap() as *const dyn FlowFactory as *const u8, b as *const dyn FlowFactory as *const u8)
+        };
+        assert!(same(table.configured("bic", &info), &*table.rules[1].factory));
+        assert!(same(table.configured("copa", &info), &*table.default));
+        let info = DatapathInfo { dst_port: 300, ..info };
+        assert!(same(table.configured("bic", &info), &*table.rules[0].factory));
+        assert!(table.configured("scalable", &info).is_none());
+    }
+}
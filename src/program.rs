@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use portus::lang::Scope;
+use portus::Report;
+
+/// A `Report` field: its initial value and how it is folded on each ACK.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub init: String,
+    /// Expressions assigned to the field in order, on every ACK.
+    pub folds: Vec<String>,
+    /// Whether the field is reset to `init` after each report.
+    pub volatile: bool,
+}
+
+impl Field {
+    /// `fold` is a portus expression for the new value, e.g.
+    /// `(+ Report.acked Ack.bytes_acked)`.
+    pub fn new(name: &str, init: &str, fold: &str) -> Self {
+        Field {
+            name: name.to_string(),
+            init: init.to_string(),
+            folds: vec![fold.to_string()],
+            volatile: true,
+        }
+    }
+
+    /// Another assignment, run after the previous ones.
+    pub fn then(mut self, fold: &str) -> Self {
+        self.folds.push(fold.to_string());
+        self
+    }
+
+    /// Keep the value across reports.
+    pub fn persistent(mut self) -> Self {
+        self.volatile = false;
+        self
+    }
+}
+
+/// When the datapath sends a report.
+#[derive(Debug, Clone)]
+pub enum Trigger {
+    /// On every ACK.
+    EveryAck,
+    /// On a loss or a timeout.
+    Urgent,
+    /// Once `reportTime` microseconds have passed; declares the `reportTime` variable.
+    Interval,
+    /// Once an RTT has passed.
+    Rtt,
+    /// When a custom portus condition holds.
+    When(String),
+}
+
+impl Trigger {
+    fn condition(&self) -> Option<String> {
+        match *self {
+            Trigger::EveryAck => None,
+            Trigger::Urgent => Some(String::from("(|| Report.timeout (> Report.loss 0))")),
+            Trigger::Interval => Some(String::from("(> Micros reportTime)")),
+            Trigger::Rtt => Some(String::from("(> Micros Flow.rtt_sample_us)")),
+            Trigger::When(ref cond) => Some(cond.clone()),
+        }
+    }
+}
+
+/// A declarative description of a datapath program, from which both the portus-lang
+/// source and the extraction of its `Report` fields are derived.
+#[derive(Debug, Clone, Default)]
+pub struct ProgramSpec {
+    fields: Vec<Field>,
+    vars: Vec<(String, String)>,
+    updates: Vec<String>,
+    triggers: Vec<Trigger>,
+}
+
+impl ProgramSpec {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn fields<I: IntoIterator<Item = Field>>(mut self, fields: I) -> Self {
+        self.fields.extend(fields);
+        self
+    }
+
+    /// A control variable which can be set when the program is installed or updated.
+    pub fn var(mut self, name: &str, init: &str) -> Self {
+        self.vars.push((name.to_string(), init.to_string()));
+        self
+    }
+
+    /// An additional assignment run on every ACK, after the field folds,
+    /// e.g. `(:= Cwnd (+ Cwnd Ack.bytes_acked))`.
+    pub fn update(mut self, update: &str) -> Self {
+        self.updates.push(update.to_string());
+        self
+    }
+
+    pub fn trigger(mut self, trigger: Trigger) -> Self {
+        if let Trigger::Interval = trigger {
+            self = self.var("reportTime", "0");
+        }
+
+        self.triggers.push(trigger);
+        self
+    }
+
+    /// Generate the portus-lang source.
+    pub fn build(&self) -> String {
+        let mut prog = String::from("(def (Report\n");
+        for f in &self.fields {
+            let volatile = if f.volatile { "volatile " } else { "" };
+            prog.push_str(&format!("    ({}{} {})\n", volatile, f.name, f.init));
+        }
+        prog.push(')');
+        for (name, init) in &self.vars {
+            prog.push_str(&format!("\n    ({} {})", name, init));
+        }
+        prog.push_str(")\n");
+
+        prog.push_str("(when true\n");
+        for f in &self.fields {
+            for fold in &f.folds {
+                prog.push_str(&format!("    (:= Report.{} {})\n", f.name, fold));
+            }
+        }
+        for u in &self.updates {
+            prog.push_str(&format!("    {}\n", u));
+        }
+
+        if self.triggers.iter().any(|t| matches!(*t, Trigger::EveryAck)) {
+            prog.push_str("    (report)\n)\n");
+            return prog;
+        }
+
+        prog.push_str("    (fallthrough)\n)\n");
+        for cond in self.triggers.iter().filter_map(Trigger::condition) {
+            prog.push_str(&format!("(when {}\n    (report)\n    (:= Micros 0)\n)\n", cond));
+        }
+
+        prog
+    }
+
+    /// Read every field of this program out of a report.
+    pub fn extract(&self, m: &Report, sc: &Scope) -> HashMap<String, u64> {
+        self.read(|name| m.get_field(name, sc).ok())
+    }
+
+    /// Read every field of this program with `get`, which is passed the field's full
+    /// name, e.g. `Report.acked`.
+    pub fn read<F: Fn(&str) -> Option<u64>>(&self, get: F) -> HashMap<String, u64> {
+        self.fields
+            .iter()
+            .map(|f| {
+                let v = get(&format!("Report.{}", f.name))
+                    .unwrap_or_else(|| panic!("expected {} field in returned measurement", f.name));
+                (f.name.clone(), v)
+            })
+            .collect()
+    }
+}
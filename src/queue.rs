@@ -0,0 +1,90 @@
+use clap::Arg;
+
+use {parse_arg, NetworkStatus};
+
+/// Drains the standing queue reported by the SDCCP controller.
+///
+/// Once utilization is at least `min_utilization`, every update removes this flow's share
+/// of the queue in excess of `target` bytes, scaled by `gain`. The share is one over the
+/// controller's reported flow count, falling back to `flows` when the controller does
+/// not report it.
+#[derive(Clone, Debug)]
+pub struct QueueController {
+    pub target: f64,
+    pub gain: f64,
+    pub min_utilization: f32,
+    pub flows: u32,
+}
+
+impl Default for QueueController {
+    fn default() -> Self {
+        QueueController {
+            target: 15_000.0,
+            gain: 0.5,
+            min_utilization: 0.9,
+            flows: 1,
+        }
+    }
+}
+
+impl QueueController {
+    pub fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("queue_control")
+                .long("queue_control")
+                .help("Drain the reported standing queue down to queue_target at high utilization"),
+            Arg::with_name("queue_target")
+                .long("queue_target")
+                .help("Target standing queue at the bottleneck, in bytes")
+                .default_value("15000"),
+            Arg::with_name("queue_gain")
+                .long("queue_gain")
+                .help("Fraction of this flow's share of the excess queue removed per update")
+                .default_value("0.5"),
+            Arg::with_name("queue_min_utilization")
+                .long("queue_min_utilization")
+                .help("Link utilization above which the queue is drained")
+                .default_value("0.9"),
+            Arg::with_name("queue_flows")
+                .long("queue_flows")
+                .help("Number of flows sharing the queue, if the controller does not report it")
+                .default_value("1"),
+        ]
+    }
+
+    /// Returns `None` unless `--queue_control` was given.
+    pub fn with_args(matches: &clap::ArgMatches) -> Result<Option<Self>, String> {
+        if !matches.is_present("queue_control") {
+            return Ok(None);
+        }
+
+        Ok(Some(QueueController {
+            target: parse_arg(matches, "queue_target")?,
+            gain: parse_arg(matches, "queue_gain")?,
+            min_utilization: parse_arg(matches, "queue_min_utilization")?,
+            flows: parse_arg(matches, "queue_flows")?,
+        }))
+    }
+
+    /// This flow's share of the queue in excess of the target, in bytes.
+    pub fn excess_share(&self, network_status: &NetworkStatus) -> f64 {
+        let excess = f64::from(network_status.queue_length) - self.target;
+        if excess <= 0.0 || network_status.link_utilization < self.min_utilization {
+            return 0.0;
+        }
+
+        let flows = if network_status.flow_count > 0 {
+            network_status.flow_count
+        } else {
+            self.flows.max(1)
+        };
+
+        excess / f64::from(flows)
+    }
+
+    /// Compute the new cwnd (bytes), never going below two packets.
+    pub fn adjust(&self, cwnd: f64, mss: u32, network_status: &NetworkStatus) -> f64 {
+        let min_cwnd = 2.0 * f64::from(mss);
+        (cwnd - self.gain * self.excess_share(network_status)).max(min_cwnd.min(cwnd))
+    }
+}
@@ -0,0 +1,152 @@
+use clap;
+use clap::Arg;
+use portus::DatapathInfo;
+use slog;
+
+use aimd::Aimd;
+use bic::Bic;
+use bin_helper::{alg_from_matches, app, start};
+use copa::Copa;
+use highspeed::HighSpeed;
+use hpcc::Hpcc;
+use illinois::Illinois;
+use learned::Learned;
+use ledbat::Ledbat;
+use pid::Pid;
+use policy::Policy;
+use reno::Reno;
+use scalable::Scalable;
+use timely::Timely;
+use westwood::Westwood;
+use {Field, GenericCongAvoidFlow, RemoteGenericCongAvoidAlg, DEFAULT_SS_THRESH};
+
+/// A type-erased `RemoteGenericCongAvoidAlg`, for choosing the flow type at runtime.
+pub trait FlowFactory {
+    fn new_boxed_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32,
+                      info: &DatapathInfo) -> Box<dyn GenericCongAvoidFlow>;
+}
+
+impl<A: RemoteGenericCongAvoidAlg> FlowFactory for A
+where
+    A::Flow: 'static,
+{
+    fn new_boxed_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32,
+                      info: &DatapathInfo) -> Box<dyn GenericCongAvoidFlow> {
+        Box::new(self.new_flow_with_info(logger, init_cwnd, info))
+    }
+}
+
+/// A `RemoteGenericCongAvoidAlg` that can be selected by name at runtime.
+pub struct Registration {
+    pub name: &'static str,
+    args: fn() -> Vec<clap::Arg<'static, 'static>>,
+    run: fn(&clap::ArgMatches, slog::Logger),
+    factory: fn(clap::ArgMatches) -> Result<Box<dyn FlowFactory>, String>,
+    extra_fields: fn() -> Vec<Field>,
+}
+
+impl Registration {
+    pub fn new<A: RemoteGenericCongAvoidAlg + 'static>() -> Self {
+        Registration {
+            name: A::name(),
+            args: A::args,
+            run: run::<A>,
+            factory: factory::<A>,
+            extra_fields: A::extra_fields,
+        }
+    }
+
+    pub fn args(&self) -> Vec<clap::Arg<'static, 'static>> {
+        (self.args)()
+    }
+
+    pub fn extra_fields(&self) -> Vec<Field> {
+        (self.extra_fields)()
+    }
+
+    /// Build the algorithm from its own arguments, given in command line syntax
+    /// (e.g. `["--ledbat_target_ms", "25"]`). Unset arguments take their defaults.
+    pub fn factory_from_args(&self, args: &[String]) -> Result<Box<dyn FlowFactory>, String> {
+        let matches = clap::App::new(self.name)
+            .args(&self.args())
+            .get_matches_from_safe(Some(self.name.to_string()).into_iter().chain(args.iter().cloned()))
+            .map_err(|e| e.message)?;
+        (self.factory)(matches)
+    }
+}
+
+fn factory<A: RemoteGenericCongAvoidAlg + 'static>(
+    matches: clap::ArgMatches,
+) -> Result<Box<dyn FlowFactory>, String> {
+    Ok(Box::new(A::with_args(matches)?))
+}
+
+fn run<A: RemoteGenericCongAvoidAlg + 'static>(matches: &clap::ArgMatches, log: slog::Logger) {
+    let (alg, ipc) = alg_from_matches::<A>(matches.clone(), log.clone())
+        .map_err(|e| warn!(log, "bad argument"; "err" => ?e))
+        .unwrap();
+
+    info!(log, "starting CCP";
+        "algorithm" => A::name(),
+        "ipc" => ipc.clone(),
+        "reports" => ?alg.report_option,
+        "slow_start_mode" => ?alg.ss,
+    );
+
+    start::<A>(ipc.as_str(), log, alg);
+}
+
+/// All algorithms in this crate.
+pub fn algorithms() -> Vec<Registration> {
+    vec![
+        Registration::new::<Aimd>(),
+        Registration::new::<Bic>(),
+        Registration::new::<Copa>(),
+        Registration::new::<HighSpeed>(),
+        Registration::new::<Hpcc>(),
+        Registration::new::<Illinois>(),
+        Registration::new::<Learned>(),
+        Registration::new::<Ledbat>(),
+        Registration::new::<Pid>(),
+        Registration::new::<Policy>(),
+        Registration::new::<Reno>(),
+        Registration::new::<Scalable>(),
+        Registration::new::<Timely>(),
+        Registration::new::<Westwood>(),
+    ]
+}
+
+/// Parse the command line, including the arguments of every registered algorithm, and
+/// run the algorithm selected with `--alg`. With `--list-algs`, print the registered
+/// algorithm names instead.
+pub fn start_selected(name: &str, log: slog::Logger) {
+    let algs = algorithms();
+    let names: Vec<&str> = algs.iter().map(|a| a.name).collect();
+    let ss_thresh_default = format!("{}", DEFAULT_SS_THRESH);
+
+    let mut app = app(name, &ss_thresh_default)
+        .arg(Arg::with_name("alg")
+             .long("alg")
+             .takes_value(true)
+             .possible_values(&names)
+             .required_unless("list_algs")
+             .help("The congestion control algorithm to run"))
+        .arg(Arg::with_name("list_algs")
+             .long("list-algs")
+             .help("List the available algorithms and exit"));
+    for alg in &algs {
+        app = app.args(&alg.args());
+    }
+
+    let matches = app.get_matches();
+    if matches.is_present("list_algs") {
+        for name in names {
+            println!("{}", name);
+        }
+        return;
+    }
+
+    let selected = matches.value_of("alg").unwrap();
+    let alg = algs.iter().find(|a| a.name == selected).unwrap();
+    (alg.run)(&matches, log);
+}
@@ -1,17 +1,15 @@
 extern crate slog;
 
-use serde_derive;
-use reqwest::Error;
-
 use std::fs::File;
-use std::io::{Write, BufReader, BufRead};
-use std::time::{SystemTime, Instant};
+use std::io::Write;
+use std::time::SystemTime;
 
 pub const LOG_OUTPUT_FILE: &str = "log_output";
 pub const TXT: &str = ".txt";
-pub const REST_ADDR: &str = "http://127.0.0.1:8080/get_user_link_utilization";
 
-use ::{RemoteGenericCongAvoidAlg, NetworkStatus};
+use ::{RemoteGenericCongAvoidAlg, NetworkStatus, StatusChange};
+use queue::QueueController;
+use utilization::UtilizationController;
 use GenericCongAvoidFlow;
 use GenericCongAvoidMeasurements;
 
@@ -21,9 +19,11 @@ pub struct Reno {
     init_cwnd: f64,
     cwnd: f64,
 
-    last_utilization: f32,
+    controller: UtilizationController,
+    queue_controller: Option<QueueController>,
+    /// The queue is drained once per controller update, not once per report.
+    status_change: StatusChange,
     log_file: Option<File>,
-    sock_id: u32,
     server_url: String,
 }
 
@@ -34,8 +34,18 @@ impl RemoteGenericCongAvoidAlg for Reno {
         "reno"
     }
 
-    fn with_args(_: clap::ArgMatches) -> Self {
-        Default::default()
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        let mut args = UtilizationController::args();
+        args.extend(QueueController::args());
+        args
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Reno {
+            controller: UtilizationController::with_args(&matches)?,
+            queue_controller: QueueController::with_args(&matches)?,
+            ..Default::default()
+        })
     }
 
     fn new_flow(&self, _logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
@@ -48,10 +58,11 @@ impl RemoteGenericCongAvoidAlg for Reno {
             init_cwnd: f64::from(init_cwnd),
             cwnd: f64::from(init_cwnd),
 
-            last_utilization: 0.0,
+            controller: self.controller.clone(),
+            queue_controller: self.queue_controller.clone(),
+            status_change: StatusChange::default(),
             log_file: File::create(&log_file).ok(),
-            sock_id,
-            server_url: format!("{}/{}", REST_ADDR, sock_id),
+            server_url: NetworkStatus::url(sock_id),
         }
     }
 }
@@ -81,94 +92,65 @@ impl GenericCongAvoidFlow for Reno {
                    network_status: &NetworkStatus,
                    m: &GenericCongAvoidMeasurements)
     {
-        let queue_length = network_status.queue_length;
-        let mut network_utilization = network_status.link_utilization;
-        if network_utilization < 0.0 {
-            network_utilization = 0.0;
+        self.cwnd = self.controller.adjust(self.cwnd, self.mss, network_status, m.acked);
+        let new_status = self.status_change.is_new(network_status);
+        if let Some(queue_controller) = self.queue_controller.as_ref().filter(|_| new_status) {
+            self.cwnd = queue_controller.adjust(self.cwnd, self.mss, network_status);
         }
 
-        {
-            // The following codes are used to test how high can cwnd be
-            let fix_cwnd :Option<u32> = None;
-            if fix_cwnd.is_some() {
-                self.cwnd = fix_cwnd.unwrap() as f64 * self.mss as f64;
-                let mut queue_packets :i32 = -1;
-                if queue_length > 0 {
-                    queue_packets = queue_length / self.mss as i32;
-                }
-                write!(self.log_file.as_mut().unwrap(),
-                       "time: {:?}\tlink_utilization: {:.2}\tqueue: {}\tcwnd: {}\trtt: {}\n",
-                       SystemTime::now(),
-                       network_status.link_utilization,
-                       queue_packets,
-                       self.cwnd as u32 / self.mss,
-                       m.rtt as f64 / 1000.0);
-                return;
-            }
+        // log
+        let mut queue_packets :i32 = 0;
+        if network_status.queue_length >= 0 {
+            queue_packets = network_status.queue_length / self.mss as i32;
         }
-
-        if network_utilization == self.last_utilization {
-            self.cwnd += f64::from(self.mss) * (f64::from(m.acked) / self.cwnd) * 1.0;
-
-            // log
-            let mut queue_packets :i32 = 0;
-            if network_status.queue_length >= 0 {
-                queue_packets = network_status.queue_length / self.mss as i32;
-            }
-            write!(self.log_file.as_mut().unwrap(),
-                   "time: {:?}\tlink_utilization: {:.2}\tqueue: {}\tcwnd: {}\trtt: {}\n",
+        if let Some(log_file) = self.log_file.as_mut() {
+            let _ = writeln!(log_file,
+                   "time: {:?}\tlink_utilization: {:.2}\tqueue: {}\tcwnd: {}\trtt: {}",
                    SystemTime::now(),
                    network_status.link_utilization,
                    queue_packets,
                    self.cwnd as u32 / self.mss,
                    m.rtt as f64 / 1000.0);
-            return;
-        }
-
-        let is_aggressive = network_utilization < 0.8;
-        if is_aggressive {
-            if network_status.link_utilization == self.last_utilization {
-            } else {
-                self.cwnd *= 3.0 / (2.0 * network_utilization as f64 + 1.0);
-            }
-        } else if network_utilization < 1.0 {
-            self.cwnd += f64::from(self.mss) * (f64::from(m.acked) / self.cwnd);
         }
+    }
 
-        if network_utilization > 1.0 {
-            let alfa = 0.3;
-            self.cwnd = alfa * self.cwnd + (1.0 - alfa) * self.cwnd / network_utilization as f64;
-        } else if (queue_length > 0) & (network_utilization > 0.9) {
-//            {
-//                println!("Link get full utilized. Decrease cwnd");
-//                self.cwnd -= f64::from(queue_length) / 3.0;
-//                if self.cwnd <= 0.0 {
-//                    self.cwnd = self.mss as f64;
-//                }
-//            }
-        }
-        self.last_utilization = network_utilization;
+    fn update_network_status(&mut self) -> NetworkStatus {
+        NetworkStatus::fetch(&self.server_url)
+    }
+}
 
-        // log
-        let mut queue_packets :i32 = 0;
-        if network_status.queue_length >= 0 {
-            queue_packets = network_status.queue_length / self.mss as i32;
+#[cfg(test)]
+mod tests {
+    use super::Reno;
+    use queue::QueueController;
+    use {GenericCongAvoidFlow, GenericCongAvoidMeasurements, NetworkStatus};
+
+    fn status(queue_length: i32) -> NetworkStatus {
+        NetworkStatus {
+            link_utilization: 0.95,
+            queue_length,
+            ..Default::default()
         }
-        write!(self.log_file.as_mut().unwrap(),
-               "time: {:?}\tlink_utilization: {:.2}\tqueue: {}\tcwnd: {}\trtt: {}\n",
-               SystemTime::now(),
-               network_status.link_utilization,
-               queue_packets,
-               self.cwnd as u32 / self.mss,
-               m.rtt as f64 / 1000.0);
     }
 
-    fn update_network_status(&mut self) -> NetworkStatus {
-        let request_url = &self.server_url;
-        let mut response = reqwest::get(request_url)
-            .expect(&format!("Failed to get response from url ({:?})", request_url));
+    #[test]
+    fn repeated_status_drains_queue_once() {
+        let mut reno = Reno {
+            mss: 1448,
+            cwnd: 1_000_000.0,
+            queue_controller: Some(QueueController::default()),
+            ..Default::default()
+        };
+        let m = GenericCongAvoidMeasurements::default();
+
+        reno.adjust_cwnd(&status(100_000), &m);
+        let drained = reno.cwnd;
+        for _ in 0..10 {
+            reno.adjust_cwnd(&status(100_000), &m);
+        }
+        assert_eq!(reno.cwnd, drained);
 
-        response.json()
-            .expect(&format!("Failed to parse the response {:?}", response))
+        reno.adjust_cwnd(&status(50_000), &m);
+        assert!(reno.cwnd < drained);
     }
 }
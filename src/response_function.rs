@@ -0,0 +1,70 @@
+use ::{GenericCongAvoidFlow, GenericCongAvoidMeasurements};
+
+/// An AIMD response function: how much to increase per RTT and by what fraction to
+/// decrease on loss, both as a function of the current window.
+pub trait ResponseFunction {
+    /// Additive increase per RTT, in packets, at a window of `w` packets.
+    fn increase(&self, w: f64) -> f64;
+    /// Fraction of the window removed on loss, at a window of `w` packets.
+    fn decrease(&self, w: f64) -> f64;
+}
+
+/// A flow which follows a `ResponseFunction` instead of fixed Reno constants.
+pub struct ResponseFunctionFlow<R: ResponseFunction> {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    init_cwnd: f64,
+    cwnd: f64,
+    response: R,
+}
+
+impl<R: ResponseFunction> ResponseFunctionFlow<R> {
+    pub fn new(logger: Option<slog::Logger>, init_cwnd: u32, mss: u32, response: R) -> Self {
+        ResponseFunctionFlow {
+            logger,
+            mss,
+            init_cwnd: f64::from(init_cwnd),
+            cwnd: f64::from(init_cwnd),
+            response,
+        }
+    }
+
+    fn window_pkts(&self) -> f64 {
+        self.cwnd / f64::from(self.mss)
+    }
+}
+
+impl<R: ResponseFunction> GenericCongAvoidFlow for ResponseFunctionFlow<R> {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn use_remote(&self) -> bool {
+        false
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        // increase cwnd by a(w) / cwnd per packet
+        let a = self.response.increase(self.window_pkts());
+        self.cwnd += a * f64::from(self.mss) * (f64::from(m.acked) / self.cwnd);
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        let b = self.response.decrease(self.window_pkts());
+        self.cwnd *= 1.0 - b;
+        if self.cwnd <= self.init_cwnd {
+            self.cwnd = self.init_cwnd;
+        }
+
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "reduction";
+                "decrease" => b,
+                "cwnd (pkts)" => self.cwnd as u32 / self.mss,
+            );
+        }
+    }
+}
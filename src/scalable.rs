@@ -0,0 +1,69 @@
+use clap::Arg;
+
+use ::{parse_arg, RemoteGenericCongAvoidAlg};
+use response_function::{ResponseFunction, ResponseFunctionFlow};
+
+/// Scalable TCP: increase by `ai` packets per acked packet, i.e. `ai * w` per RTT, and
+/// cut the window by `md` on loss. Behaves like Reno up to `low_window` packets.
+#[derive(Clone)]
+pub struct Scalable {
+    ai: f64,
+    md: f64,
+    low_window: f64,
+}
+
+impl ResponseFunction for Scalable {
+    fn increase(&self, w: f64) -> f64 {
+        if w <= self.low_window {
+            1.0
+        } else {
+            self.ai * w
+        }
+    }
+
+    fn decrease(&self, w: f64) -> f64 {
+        if w <= self.low_window {
+            0.5
+        } else {
+            self.md
+        }
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Scalable {
+    type Flow = ResponseFunctionFlow<Scalable>;
+
+    fn name() -> &'static str {
+        "scalable"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("scalable_ai")
+                .long("scalable_ai")
+                .help("Increase per acked packet, in packets")
+                .default_value("0.01"),
+            Arg::with_name("scalable_md")
+                .long("scalable_md")
+                .help("Fraction of the window removed on loss")
+                .default_value("0.125"),
+            Arg::with_name("scalable_low_window")
+                .long("scalable_low_window")
+                .help("Window, in packets, up to which Scalable TCP behaves like Reno")
+                .default_value("16"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Scalable {
+            ai: parse_arg(&matches, "scalable_ai")?,
+            md: parse_arg(&matches, "scalable_md")?,
+            low_window: parse_arg(&matches, "scalable_low_window")?,
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                _sock_id: u32) -> Self::Flow {
+        ResponseFunctionFlow::new(logger, init_cwnd, mss, self.clone())
+    }
+}
@@ -0,0 +1,211 @@
+use clap::Arg;
+use time::{self, Timespec};
+
+use ::{parse_arg, FlowHandoff, RemoteGenericCongAvoidAlg, GenericCongAvoidFlow,
+       GenericCongAvoidMeasurements};
+
+/// Number of consecutive non-positive gradients before hyperactive increase kicks in.
+const HAI_THRESHOLD: u32 = 5;
+
+/// Timely: react to the RTT gradient between successive samples.
+///
+/// Below `t_low` the window grows additively and above `t_high` it is cut in proportion
+/// to how far the RTT exceeds `t_high`. In between, a negative gradient grows the window
+/// and a positive one shrinks it by `beta * normalized_gradient`. Updates are applied
+/// at most once per RTT, however frequently the datapath reports.
+pub struct Timely {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    init_cwnd: f64,
+    cwnd: f64,
+
+    t_low: f64,
+    t_high: f64,
+    alpha: f64,
+    beta: f64,
+    additive_increase: f64,
+
+    prev_rtt: f64,
+    rtt_diff: f64,
+    min_rtt: f64,
+    negative_gradients: u32,
+    last_update: Timespec,
+}
+
+impl Default for Timely {
+    fn default() -> Self {
+        Timely {
+            logger: None,
+            mss: 0,
+            init_cwnd: 0.0,
+            cwnd: 0.0,
+
+            t_low: 50.0,
+            t_high: 500.0,
+            alpha: 0.875,
+            beta: 0.8,
+            additive_increase: 1.0,
+
+            prev_rtt: 0.0,
+            rtt_diff: 0.0,
+            min_rtt: 0.0,
+            negative_gradients: 0,
+            last_update: time::now().to_timespec(),
+        }
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Timely {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "timely"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("timely_t_low")
+                .long("timely_t_low")
+                .help("RTT (us) below which the window always increases additively")
+                .default_value("50"),
+            Arg::with_name("timely_t_high")
+                .long("timely_t_high")
+                .help("RTT (us) above which the window always decreases multiplicatively")
+                .default_value("500"),
+            Arg::with_name("timely_alpha")
+                .long("timely_alpha")
+                .help("EWMA weight of the newest RTT difference")
+                .default_value("0.875"),
+            Arg::with_name("timely_beta")
+                .long("timely_beta")
+                .help("Multiplicative decrease factor")
+                .default_value("0.8"),
+            Arg::with_name("timely_ai")
+                .long("timely_ai")
+                .help("Additive increase step, in packets")
+                .default_value("1"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Timely {
+            t_low: parse_arg(&matches, "timely_t_low")?,
+            t_high: parse_arg(&matches, "timely_t_high")?,
+            alpha: parse_arg(&matches, "timely_alpha")?,
+            beta: parse_arg(&matches, "timely_beta")?,
+            additive_increase: parse_arg(&matches, "timely_ai")?,
+            ..Default::default()
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                _sock_id: u32) -> Self::Flow {
+        Timely {
+            logger,
+            mss,
+            init_cwnd: f64::from(init_cwnd),
+            cwnd: f64::from(init_cwnd),
+
+            t_low: self.t_low,
+            t_high: self.t_high,
+            alpha: self.alpha,
+            beta: self.beta,
+            additive_increase: self.additive_increase,
+            ..Default::default()
+        }
+    }
+}
+
+impl GenericCongAvoidFlow for Timely {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn use_remote(&self) -> bool {
+        false
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        if m.rtt == 0 {
+            return;
+        }
+
+        let new_rtt = f64::from(m.rtt);
+        if self.min_rtt == 0.0 || new_rtt < self.min_rtt {
+            self.min_rtt = new_rtt;
+        }
+
+        if self.prev_rtt == 0.0 {
+            self.prev_rtt = new_rtt;
+            return;
+        }
+
+        let now = time::now().to_timespec();
+        if (now - self.last_update) < time::Duration::microseconds(i64::from(m.rtt)) {
+            return;
+        }
+
+        let new_rtt_diff = new_rtt - self.prev_rtt;
+        self.prev_rtt = new_rtt;
+        self.rtt_diff = (1.0 - self.alpha) * self.rtt_diff + self.alpha * new_rtt_diff;
+        let gradient = self.rtt_diff / self.min_rtt;
+        let step = self.additive_increase * f64::from(self.mss);
+
+        if new_rtt < self.t_low {
+            self.negative_gradients = 0;
+            self.cwnd += step;
+        } else if new_rtt > self.t_high {
+            self.negative_gradients = 0;
+            self.cwnd *= 1.0 - self.beta * (1.0 - self.t_high / new_rtt);
+        } else if gradient <= 0.0 {
+            self.negative_gradients += 1;
+            let n = if self.negative_gradients >= HAI_THRESHOLD { 5.0 } else { 1.0 };
+            self.cwnd += n * step;
+        } else {
+            self.negative_gradients = 0;
+            self.cwnd *= 1.0 - self.beta * gradient.min(1.0);
+        }
+
+        let min_cwnd = f64::from(self.mss);
+        if self.cwnd < min_cwnd {
+            self.cwnd = min_cwnd;
+        }
+
+        self.last_update = now;
+
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "timely";
+                "rtt" => m.rtt,
+                "gradient" => gradient,
+                "cwnd (pkts)" => self.cwnd as u32 / self.mss,
+            );
+        }
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        self.cwnd /= 2.0;
+        if self.cwnd <= self.init_cwnd {
+            self.cwnd = self.init_cwnd;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.prev_rtt = 0.0;
+        self.rtt_diff = 0.0;
+        self.negative_gradients = 0;
+    }
+
+    /// Start the gradient from the previous algorithm's smoothed RTT, which also bounds
+    /// the minimum RTT until a smaller sample arrives.
+    fn import_state(&mut self, state: &FlowHandoff) {
+        self.set_cwnd(state.cwnd);
+        if state.srtt > 0 {
+            self.prev_rtt = f64::from(state.srtt);
+            self.min_rtt = self.prev_rtt;
+        }
+    }
+}
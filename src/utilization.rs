@@ -0,0 +1,128 @@
+use clap::Arg;
+
+use {parse_arg, parse_opt_arg, NetworkStatus};
+
+/// Drives cwnd from the link utilization reported by the SDCCP controller.
+///
+/// * While utilization is unchanged since the last controller update, grow like Reno.
+/// * Below `aggressive_below`, scale cwnd by `3 / (2u + 1)` to quickly fill the link.
+/// * Between `aggressive_below` and `target`, grow like Reno.
+/// * Above `target`, move cwnd towards `cwnd * target / u`, by `gain` of the difference.
+/// * If `drain_queue` is set and the link is above `drain_above` with a standing queue,
+///   additionally remove `drain_fraction` of the reported queue from cwnd.
+///
+/// `fixed_cwnd` pins cwnd to a number of packets, to test how high cwnd can go.
+/// The defaults reproduce the original hardcoded Reno controller.
+#[derive(Clone, Debug)]
+pub struct UtilizationController {
+    pub aggressive_below: f32,
+    pub target: f32,
+    pub gain: f64,
+    pub drain_queue: bool,
+    pub drain_above: f32,
+    pub drain_fraction: f64,
+    pub fixed_cwnd: Option<u32>,
+
+    last_utilization: f32,
+}
+
+impl Default for UtilizationController {
+    fn default() -> Self {
+        UtilizationController {
+            aggressive_below: 0.8,
+            target: 1.0,
+            gain: 0.7,
+            drain_queue: false,
+            drain_above: 0.9,
+            drain_fraction: 1.0 / 3.0,
+            fixed_cwnd: None,
+
+            last_utilization: 0.0,
+        }
+    }
+}
+
+impl UtilizationController {
+    pub fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("util_aggressive_below")
+                .long("util_aggressive_below")
+                .help("Link utilization below which cwnd is scaled up multiplicatively")
+                .default_value("0.8"),
+            Arg::with_name("util_target")
+                .long("util_target")
+                .help("Link utilization above which cwnd is decreased")
+                .default_value("1.0"),
+            Arg::with_name("util_gain")
+                .long("util_gain")
+                .help("Fraction of the distance to cwnd * target / utilization covered per update when above target")
+                .default_value("0.7"),
+            Arg::with_name("util_drain_queue")
+                .long("util_drain_queue")
+                .help("Remove part of the reported standing queue from cwnd when the link is nearly full"),
+            Arg::with_name("util_drain_above")
+                .long("util_drain_above")
+                .help("Link utilization above which the standing queue is drained")
+                .default_value("0.9"),
+            Arg::with_name("util_drain_fraction")
+                .long("util_drain_fraction")
+                .help("Fraction of the reported queue (bytes) removed from cwnd per update")
+                .default_value("0.333"),
+            Arg::with_name("fix_cwnd")
+                .long("fix_cwnd")
+                .takes_value(true)
+                .help("Debug mode: pin cwnd to this many packets, ignoring the controller"),
+        ]
+    }
+
+    pub fn with_args(matches: &clap::ArgMatches) -> Result<Self, String> {
+        Ok(UtilizationController {
+            aggressive_below: parse_arg(matches, "util_aggressive_below")?,
+            target: parse_arg(matches, "util_target")?,
+            gain: parse_arg(matches, "util_gain")?,
+            drain_queue: matches.is_present("util_drain_queue"),
+            drain_above: parse_arg(matches, "util_drain_above")?,
+            drain_fraction: parse_arg(matches, "util_drain_fraction")?,
+            fixed_cwnd: parse_opt_arg(matches, "fix_cwnd")?,
+            ..Default::default()
+        })
+    }
+
+    /// Compute the new cwnd (bytes) given the controller's report and the bytes acked
+    /// since the last report.
+    pub fn adjust(&mut self, cwnd: f64, mss: u32, network_status: &NetworkStatus, acked: u32) -> f64 {
+        let mss = f64::from(mss);
+        if let Some(fixed) = self.fixed_cwnd {
+            return f64::from(fixed) * mss;
+        }
+
+        let utilization = network_status.link_utilization.max(0.0);
+        let reno_increase = mss * (f64::from(acked) / cwnd);
+        if utilization == self.last_utilization {
+            return cwnd + reno_increase;
+        }
+
+        let mut cwnd = cwnd;
+        if utilization < self.aggressive_below {
+            cwnd *= 3.0 / (2.0 * f64::from(utilization) + 1.0);
+        } else if utilization < self.target {
+            cwnd += reno_increase;
+        }
+
+        if utilization > self.target {
+            let corrected = cwnd * f64::from(self.target) / f64::from(utilization);
+            cwnd = (1.0 - self.gain) * cwnd + self.gain * corrected;
+        } else if self.drain_queue
+            && network_status.queue_length > 0
+            && utilization > self.drain_above
+        {
+            cwnd -= f64::from(network_status.queue_length) * self.drain_fraction;
+            if cwnd <= 0.0 {
+                cwnd = mss;
+            }
+        }
+
+        self.last_utilization = utilization;
+        cwnd
+    }
+}
@@ -0,0 +1,159 @@
+use clap::Arg;
+use time::{self, Timespec};
+
+use ::{parse_arg, FlowHandoff, RemoteGenericCongAvoidAlg, GenericCongAvoidFlow,
+       GenericCongAvoidMeasurements};
+
+/// Westwood+: Reno increase, with the post-loss window set from a bandwidth estimate.
+///
+/// Acked bytes are accumulated across reports and turned into a bandwidth sample once
+/// per RTT, then low-pass filtered. On loss, cwnd (and hence ss_thresh) becomes
+/// `BWE * RTTmin`. Works with any report mode, but is meant to be run with
+/// `--report_interval_ms` so samples do not depend on per-ACK reporting.
+pub struct Westwood {
+    logger: Option<slog::Logger>,
+    mss: u32,
+    init_cwnd: f64,
+    cwnd: f64,
+
+    filter_gain: f64,
+
+    bwe: f64,
+    rtt_min: u32,
+    acked_since_sample: u32,
+    last_sample: Timespec,
+}
+
+impl Default for Westwood {
+    fn default() -> Self {
+        Westwood {
+            logger: None,
+            mss: 0,
+            init_cwnd: 0.0,
+            cwnd: 0.0,
+
+            filter_gain: 0.9,
+
+            bwe: 0.0,
+            rtt_min: 0,
+            acked_since_sample: 0,
+            last_sample: time::now().to_timespec(),
+        }
+    }
+}
+
+impl RemoteGenericCongAvoidAlg for Westwood {
+    type Flow = Self;
+
+    fn name() -> &'static str {
+        "westwood"
+    }
+
+    fn args<'a, 'b>() -> Vec<clap::Arg<'a, 'b>> {
+        vec![
+            Arg::with_name("westwood_filter_gain")
+                .long("westwood_filter_gain")
+                .help("Weight of the previous bandwidth estimate in the low-pass filter")
+                .default_value("0.9"),
+        ]
+    }
+
+    fn with_args(matches: clap::ArgMatches) -> Result<Self, String> {
+        Ok(Westwood {
+            filter_gain: parse_arg(&matches, "westwood_filter_gain")?,
+            ..Default::default()
+        })
+    }
+
+    fn new_flow(&self, logger: Option<slog::Logger>, init_cwnd: u32, mss: u32,
+                _sock_id: u32) -> Self::Flow {
+        Westwood {
+            logger,
+            mss,
+            init_cwnd: f64::from(init_cwnd),
+            cwnd: f64::from(init_cwnd),
+            filter_gain: self.filter_gain,
+            ..Default::default()
+        }
+    }
+}
+
+impl Westwood {
+    fn update_bwe(&mut self, m: &GenericCongAvoidMeasurements) {
+        if m.rtt_min > 0 && (self.rtt_min == 0 || m.rtt_min < self.rtt_min) {
+            self.rtt_min = m.rtt_min;
+        }
+
+        self.acked_since_sample += m.acked;
+        let now = time::now().to_timespec();
+        let elapsed = (now - self.last_sample).num_microseconds().unwrap_or(0);
+        if elapsed <= 0 || elapsed < i64::from(m.rtt) {
+            return;
+        }
+
+        // bytes per microsecond
+        let sample = f64::from(self.acked_since_sample) / elapsed as f64;
+        self.bwe = if self.bwe == 0.0 {
+            sample
+        } else {
+            self.filter_gain * self.bwe + (1.0 - self.filter_gain) * sample
+        };
+
+        self.acked_since_sample = 0;
+        self.last_sample = now;
+    }
+}
+
+impl GenericCongAvoidFlow for Westwood {
+    fn curr_cwnd(&self) -> u32 {
+        self.cwnd as u32
+    }
+
+    fn set_cwnd(&mut self, cwnd: u32) {
+        self.cwnd = f64::from(cwnd);
+    }
+
+    fn use_remote(&self) -> bool {
+        false
+    }
+
+    fn increase(&mut self, m: &GenericCongAvoidMeasurements) {
+        self.update_bwe(m);
+
+        // increase cwnd by 1 / cwnd per packet
+        self.cwnd += f64::from(self.mss) * (f64::from(m.acked) / self.cwnd);
+    }
+
+    fn reduction(&mut self, _m: &GenericCongAvoidMeasurements) {
+        let min_cwnd = 2.0 * f64::from(self.mss);
+        let bdp = self.bwe * f64::from(self.rtt_min);
+        if bdp > 0.0 {
+            self.cwnd = self.cwnd.min(bdp).max(min_cwnd);
+        } else {
+            // no estimate yet: fall back to Reno
+            self.cwnd = (self.cwnd / 2.0).max(self.init_cwnd);
+        }
+
+        if let Some(log) = self.logger.as_ref() {
+            debug!(log, "westwood reduction";
+                "bwe (bytes/us)" => self.bwe,
+                "rtt_min" => self.rtt_min,
+                "cwnd (pkts)" => self.cwnd as u32 / self.mss,
+            );
+        }
+    }
+
+    fn reset(&mut self) {
+        self.acked_since_sample = 0;
+        self.last_sample = time::now().to_timespec();
+    }
+
+    /// The previous algorithm's smoothed RTT bounds the minimum RTT until a smaller
+    /// sample arrives.
+    fn import_state(&mut self, state: &FlowHandoff) {
+        self.set_cwnd(state.cwnd);
+        if state.srtt > 0 {
+            self.rtt_min = state.srtt;
+        }
+    }
+}